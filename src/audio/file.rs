@@ -1,117 +1,313 @@
-use std::path::Path;
+use std::{fmt, path::Path};
 
-use anyhow::{Error, Result};
 use symphonia::{
     core::{
-        audio::SampleBuffer,
-        codecs::{Decoder, DecoderOptions},
-        errors::Error::DecodeError,
-        formats::{FormatOptions, FormatReader},
+        audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, SampleBuffer, Signal, SignalSpec},
+        codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+        errors::Error as SymphoniaError,
+        formats::{FormatOptions, FormatReader, SeekMode, SeekTo, Track},
+        io::MediaSource,
         meta::MetadataOptions,
-        probe::Hint,
+        probe::{Hint, ProbeResult},
+        units::Time,
     },
     default::{get_codecs, get_probe},
 };
 
-use crate::resource;
+use crate::{
+    error::{PhonolyzeError, Result},
+    resource,
+};
+
+use super::{metadata::Metadata, CopyMethod};
+
+/// Description of one audio track in a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackInfo {
+    /// Position among the audio tracks of the file, as taken by `--track`
+    pub index: usize,
+    pub codec: String,
+    pub channels: Option<usize>,
+    pub sample_rate: Option<u32>,
+    pub language: Option<String>,
+}
+
+impl TrackInfo {
+    fn new(index: usize, track: &Track) -> Self {
+        let params = &track.codec_params;
+
+        TrackInfo {
+            index,
+            codec: get_codecs().get_codec(params.codec).map_or_else(
+                || params.codec.to_string(),
+                |codec| codec.short_name.to_string(),
+            ),
+            channels: params.channels.map(|channels| channels.count()),
+            sample_rate: params.sample_rate,
+            language: track.language.clone(),
+        }
+    }
+}
 
-use super::CopyMethod;
+impl fmt::Display for TrackInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}", self.index, self.codec)?;
+        if let Some(channels) = self.channels {
+            write!(f, ", {channels} ch")?;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            write!(f, ", {sample_rate} Hz")?;
+        }
+        if let Some(language) = &self.language {
+            write!(f, ", {language}")?;
+        }
+        Ok(())
+    }
+}
 
 pub struct AudioFile {
     format: Box<dyn FormatReader>,
     pub decoder: Box<dyn Decoder>,
-    default_track_id: u32,
+    track_id: u32,
+    track: TrackInfo,
+    sample_rate: u32,
+    channels: usize,
+    /// Size of the file in bytes, if the source knows it
+    byte_len: Option<u64>,
+    metadata: Metadata,
+    /// Times in seconds of packets that failed to decode and were skipped
+    corrupt_packets: Vec<f64>,
 }
 
 impl AudioFile {
     pub fn sample_rate(&self) -> u32 {
-        self.decoder.codec_params().sample_rate.unwrap()
+        self.sample_rate
     }
 
     pub fn channels(&self) -> usize {
-        self.decoder.codec_params().channels.unwrap().count()
+        self.channels
+    }
+
+    /// Length of the track in frames, if the container knows it.
+    pub fn n_frames(&self) -> Option<u64> {
+        self.decoder.codec_params().n_frames
+    }
+
+    /// Bits per sample of the encoded audio, for lossless codecs.
+    pub fn bits_per_sample(&self) -> Option<u32> {
+        self.decoder.codec_params().bits_per_sample
+    }
+
+    /// Length of the track in seconds, if the container knows it.
+    pub fn duration(&self) -> Option<f64> {
+        Some(self.ts_seconds(self.n_frames()?))
+    }
+
+    /// Bitrate of the track in bits per second, for codecs whose samples
+    /// all take the same number of bits.  Symphonia doesn't report the
+    /// bitrate of other codecs.
+    pub fn stream_bitrate(&self) -> Option<f64> {
+        let bits = self.decoder.codec_params().bits_per_coded_sample?;
+
+        Some(bits as f64 * self.sample_rate as f64 * self.channels as f64)
+    }
+
+    /// Size of the whole file in bits per second of the track, container,
+    /// tags and any other tracks included.
+    pub fn file_bitrate(&self) -> Option<f64> {
+        Some(self.byte_len? as f64 * 8. / self.duration()?).filter(|bitrate| bitrate.is_finite())
+    }
+
+    /// Tags and cover art from the container and any tags before it.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
     }
 
-    pub async fn open(path: &str) -> Result<Self> {
-        let source = resource::load_sound(path).await?;
-        let hint = Hint::new();
-        let format_opts: FormatOptions = Default::default();
-        let metadata_opts: MetadataOptions = Default::default();
+    /// The track being decoded.
+    pub fn track(&self) -> &TrackInfo {
+        &self.track
+    }
+
+    /// Every audio track in the file.
+    pub fn tracks(&self) -> Vec<TrackInfo> {
+        track_infos(self.format.tracks())
+    }
+
+    /// Every audio track in a file, as the container lists them, without
+    /// opening any of them.
+    pub async fn list_tracks(path: &str) -> Result<Vec<TrackInfo>> {
+        let (probed, _) = probe(path).await?;
+
+        Ok(track_infos(probed.format.tracks()))
+    }
+
+    /// Open the audio track at `index` as listed by `tracks`, or the
+    /// default track.
+    pub async fn open_track(path: &str, index: Option<usize>) -> Result<Self> {
+        let (mut probed, byte_len) = probe(path).await?;
         let decoder_opts: DecoderOptions = Default::default();
-        let format = get_probe()
-            .format(&hint, source, &format_opts, &metadata_opts)
-            .unwrap()
-            .format;
-        let track = format
-            .default_track()
-            .ok_or_else(|| Error::msg("No default track."))?;
+        let mut metadata = Metadata::default();
+
+        if let Some(revision) = probed.format.metadata().current() {
+            metadata.merge(revision);
+        }
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|log| log.current()) {
+            metadata.merge(revision);
+        }
+
+        let format = probed.format;
+        let (index, track) = match index {
+            Some(index) => audio_tracks(format.tracks()).enumerate().nth(index),
+            None => format.default_track().and_then(|default| {
+                audio_tracks(format.tracks())
+                    .enumerate()
+                    .find(|(_, track)| track.id == default.id)
+            }),
+        }
+        .ok_or(PhonolyzeError::MissingTrack)?;
+        let (sample_rate, channels) = signal_format(track)?;
         let decoder = get_codecs().make(&track.codec_params, &decoder_opts)?;
-        let default_track_id = track.id;
+        let track_id = track.id;
+        let track = TrackInfo::new(index, track);
 
         Ok(AudioFile {
             format,
             decoder,
-            default_track_id,
+            track_id,
+            track,
+            sample_rate,
+            channels,
+            byte_len,
+            metadata,
+            corrupt_packets: Vec::new(),
         })
     }
 
-    pub fn next_sample(&mut self, meth: CopyMethod) -> Result<Option<SampleBuffer<f32>>> {
-        let packet = self.format.next_packet()?;
-        if packet.track_id() != self.default_track_id {
-            return Ok(None);
+    /// Seek to a time in seconds.  Seeking lands on a packet boundary at or
+    /// before the time, so this returns how many frames to discard after it.
+    pub fn seek(&mut self, seconds: f64) -> Result<usize> {
+        let seeked = self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: Time::new(seconds.trunc() as u64, seconds.fract()),
+                track_id: Some(self.track_id),
+            },
+        )?;
+        self.decoder.reset();
+
+        let early = seeked.required_ts.saturating_sub(seeked.actual_ts);
+
+        Ok((self.ts_seconds(early) * self.sample_rate() as f64).round() as usize)
+    }
+
+    /// Seconds in a track timestamp.
+    fn ts_seconds(&self, ts: u64) -> f64 {
+        match self.decoder.codec_params().time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(ts);
+                time.seconds as f64 + time.frac
+            }
+            None => ts as f64 / self.sample_rate() as f64,
         }
-        match self.decoder.decode(&packet) {
-            Ok(audio_buf_ref) => {
-                let spec = *audio_buf_ref.spec();
-                let duration = audio_buf_ref.capacity() as u64;
-                let mut buf = SampleBuffer::new(duration, spec);
-                if let CopyMethod::Interleaved = meth {
-                    buf.copy_interleaved_ref(audio_buf_ref);
-                } else if let CopyMethod::Planar = meth {
-                    buf.copy_planar_ref(audio_buf_ref);
+    }
+
+    /// Times in seconds of the packets skipped so far because they failed
+    /// to decode.
+    pub fn corrupt_packets(&self) -> &[f64] {
+        &self.corrupt_packets
+    }
+
+    /// Decode the next packet of the track, or None at the end of the
+    /// stream.  Corrupt packets are recorded and replaced with silence, and
+    /// the track is picked again when the stream changes its tracks.
+    pub fn next_sample(&mut self, meth: CopyMethod) -> Result<Option<SampleBuffer<f32>>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(None);
                 }
-                Ok(Some(buf))
+                Err(SymphoniaError::ResetRequired) => {
+                    self.reselect_track()?;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            match self.decoder.decode(&packet) {
+                Ok(audio_buf_ref) => return Ok(Some(copy(audio_buf_ref, meth))),
+                Err(SymphoniaError::DecodeError(_)) => {
+                    self.corrupt_packets.push(self.ts_seconds(packet.ts()));
+
+                    // Keep the samples after it at their time.
+                    let Some(layout) = self.decoder.codec_params().channels else {
+                        continue;
+                    };
+                    if packet.dur > 0 {
+                        let spec = SignalSpec::new(self.sample_rate, layout);
+                        let mut silence = AudioBuffer::<f32>::new(packet.dur, spec);
+
+                        silence.render_silence(None);
+                        return Ok(Some(copy(silence.as_audio_buffer_ref(), meth)));
+                    }
+                }
+                Err(SymphoniaError::ResetRequired) => self.reselect_track()?,
+                Err(e) => return Err(e.into()),
             }
-            Err(DecodeError(_)) => Ok(None),
-            Err(_) => Err(Error::msg("Decode error.")),
         }
     }
 
+    /// Find the track again after the stream changed its list of tracks,
+    /// with a new decoder for its current codec parameters.
+    fn reselect_track(&mut self) -> Result<()> {
+        let (index, track) = audio_tracks(self.format.tracks())
+            .enumerate()
+            .find(|(_, track)| track.id == self.track_id)
+            .or_else(|| {
+                audio_tracks(self.format.tracks())
+                    .enumerate()
+                    .nth(self.track.index)
+            })
+            .ok_or(PhonolyzeError::MissingTrack)?;
+        let (sample_rate, channels) = signal_format(track)?;
+
+        self.decoder = get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+        self.track_id = track.id;
+        self.track = TrackInfo::new(index, track);
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+
+        Ok(())
+    }
+
     #[allow(unused)]
     pub fn dump(&mut self) -> (Vec<f32>, Vec<f32>) {
         let mut left = Vec::new();
         let mut right = Vec::new();
-        while let Ok(buf) = self.next_sample(CopyMethod::Planar) {
-            if let Some(buf) = buf {
-                let s = buf.samples();
-                left.append(&mut Vec::from(&s[..s.len() / 2]));
-                right.append(&mut Vec::from(&s[s.len() / 2..]));
-            }
+        while let Ok(Some(buf)) = self.next_sample(CopyMethod::Planar) {
+            let s = buf.samples();
+            left.append(&mut Vec::from(&s[..s.len() / 2]));
+            right.append(&mut Vec::from(&s[s.len() / 2..]));
         }
         (left, right)
     }
 
-    pub fn dump_mono(&mut self, seconds: Option<f32>) -> Vec<f32> {
-        let mut result = Vec::new();
-        let sample_limit = seconds.map(|s| (s * self.sample_rate() as f32).round() as u32);
-        let mut count = 0u32;
-
-        // Just grab the left channel.
-        while let Ok(buf) = self.next_sample(CopyMethod::Planar) {
-            if let Some(buf) = buf {
-                let mut samples = Vec::from(buf.samples());
-                let len = samples.len();
-                result.append(&mut Vec::from(&mut samples[0..len]));
-                if let Some(limit) = sample_limit {
-                    count += buf.len() as u32;
-                    if count > limit {
-                        break;
-                    }
-                }
-            }
-        }
+    /// Decode a mono mixdown from the current position packet by packet,
+    /// dropping the first `skip` frames and keeping exactly `seconds` if
+    /// given.
+    pub fn stream_mono(&mut self, skip: usize, seconds: Option<f32>) -> MonoStream<'_> {
+        let remaining = seconds.map(|s| (s * self.sample_rate() as f32).round() as usize);
 
-        result
+        MonoStream {
+            audio: self,
+            skip,
+            remaining,
+        }
     }
 
     #[allow(unused)]
@@ -134,3 +330,142 @@ impl AudioFile {
         writer.finalize().unwrap();
     }
 }
+
+/// Container of a file with any tags before it, and the size of the file.
+async fn probe(path: &str) -> Result<(ProbeResult, Option<u64>)> {
+    let source = resource::load_sound(path).await?;
+    let byte_len = source.byte_len();
+    let hint = Hint::new();
+    let format_opts: FormatOptions = Default::default();
+    let metadata_opts: MetadataOptions = Default::default();
+    let probed = get_probe().format(&hint, source, &format_opts, &metadata_opts)?;
+
+    Ok((probed, byte_len))
+}
+
+fn track_infos(tracks: &[Track]) -> Vec<TrackInfo> {
+    audio_tracks(tracks)
+        .enumerate()
+        .map(|(index, track)| TrackInfo::new(index, track))
+        .collect()
+}
+
+/// Sample rate and channel count of a track, which decoding needs.
+fn signal_format(track: &Track) -> Result<(u32, usize)> {
+    let params = &track.codec_params;
+    let sample_rate = params
+        .sample_rate
+        .ok_or(PhonolyzeError::MissingSampleRate)?;
+    let channels = params
+        .channels
+        .ok_or(PhonolyzeError::MissingChannels)?
+        .count();
+
+    Ok((sample_rate, channels))
+}
+
+/// Copy decoded audio out of the decoder's buffer.
+fn copy(audio_buf_ref: AudioBufferRef, meth: CopyMethod) -> SampleBuffer<f32> {
+    let spec = *audio_buf_ref.spec();
+    let duration = audio_buf_ref.capacity() as u64;
+    let mut buf = SampleBuffer::new(duration, spec);
+    if let CopyMethod::Interleaved = meth {
+        buf.copy_interleaved_ref(audio_buf_ref);
+    } else if let CopyMethod::Planar = meth {
+        buf.copy_planar_ref(audio_buf_ref);
+    }
+    buf
+}
+
+/// Tracks with a codec, skipping subtitles and other data.
+fn audio_tracks(tracks: &[Track]) -> impl Iterator<Item = &Track> {
+    tracks
+        .iter()
+        .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+}
+
+/// Left channel of an `AudioFile`, one decoded packet at a time.
+pub struct MonoStream<'a> {
+    audio: &'a mut AudioFile,
+    skip: usize,
+    remaining: Option<usize>,
+}
+
+impl Iterator for MonoStream<'_> {
+    type Item = Vec<f32>;
+
+    fn next(&mut self) -> Option<Vec<f32>> {
+        while self.remaining != Some(0) {
+            let buf = match self.audio.next_sample(CopyMethod::Planar) {
+                Ok(Some(buf)) => buf,
+                Ok(None) => return None,
+                Err(e) => {
+                    log::error!("Stopped decoding: {e}");
+                    return None;
+                }
+            };
+            let mono = mixdown(buf.samples(), self.audio.channels());
+            let skipped = self.skip.min(mono.len());
+            let mut mono = mono[skipped..].to_vec();
+
+            self.skip -= skipped;
+            if let Some(remaining) = &mut self.remaining {
+                mono.truncate(*remaining);
+                *remaining -= mono.len();
+            }
+            if !mono.is_empty() {
+                return Some(mono);
+            }
+        }
+
+        None
+    }
+}
+
+/// Average of the channels of planar samples, frame by frame.
+fn mixdown(planar: &[f32], channels: usize) -> Vec<f32> {
+    let channels = channels.max(1);
+    let frames = planar.len() / channels;
+
+    (0..frames)
+        .map(|frame| {
+            (0..channels)
+                .map(|channel| planar[channel * frames + frame])
+                .sum::<f32>()
+                / channels as f32
+        })
+        .collect()
+}
+
+/// Write a mono signal as a 32-bit float WAV file.
+pub fn save_wav(path: &str, signal: &[f32], sample_rate: u32) -> hound::Result<()> {
+    let mut writer = hound::WavWriter::create(
+        path,
+        hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        },
+    )?;
+
+    for sample in signal {
+        writer.write_sample(*sample)?;
+    }
+
+    writer.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixdown_averages_every_channel() {
+        // Left silent, right a ramp.
+        let planar = [0., 0., 0., 0.2, 0.4, 0.6];
+
+        assert_eq!(mixdown(&planar, 2), vec![0.1, 0.2, 0.3]);
+        assert_eq!(mixdown(&planar, 1), planar.to_vec());
+    }
+}
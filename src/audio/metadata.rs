@@ -0,0 +1,92 @@
+//! Tags and pictures embedded in audio files.
+use symphonia::core::meta::{MetadataRevision, StandardTagKey, StandardVisualKey};
+
+/// Tags and cover art of a file.
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Encoded picture, the front cover if there is one
+    pub cover: Option<Box<[u8]>>,
+}
+
+impl Metadata {
+    /// Fill in whatever is still missing from a revision.
+    pub fn merge(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                _ => continue,
+            };
+            field.get_or_insert_with(|| tag.value.to_string());
+        }
+
+        let visuals = revision.visuals();
+        let cover = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or(visuals.first());
+
+        if let (None, Some(cover)) = (&self.cover, cover) {
+            self.cover = Some(cover.data.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::meta::{MetadataBuilder, Tag, Value, Visual};
+
+    fn visual(usage: StandardVisualKey, data: &[u8]) -> Visual {
+        Visual {
+            media_type: "image/png".to_string(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage: Some(usage),
+            tags: Vec::new(),
+            data: data.into(),
+        }
+    }
+
+    #[test]
+    fn merge_keeps_the_first_values_and_the_front_cover() {
+        let mut builder = MetadataBuilder::new();
+        builder
+            .add_tag(Tag::new(
+                Some(StandardTagKey::TrackTitle),
+                "TITLE",
+                Value::from("Container title"),
+            ))
+            .add_visual(visual(StandardVisualKey::BackCover, &[1]))
+            .add_visual(visual(StandardVisualKey::FrontCover, &[2]));
+        let container = builder.metadata();
+        let mut builder = MetadataBuilder::new();
+        builder
+            .add_tag(Tag::new(
+                Some(StandardTagKey::TrackTitle),
+                "TIT2",
+                Value::from("Tag title"),
+            ))
+            .add_tag(Tag::new(
+                Some(StandardTagKey::Artist),
+                "TPE1",
+                Value::from("Artist"),
+            ))
+            .add_visual(visual(StandardVisualKey::FrontCover, &[3]));
+        let tags = builder.metadata();
+        let mut metadata = Metadata::default();
+
+        metadata.merge(&container);
+        metadata.merge(&tags);
+
+        assert_eq!(metadata.title.as_deref(), Some("Container title"));
+        assert_eq!(metadata.artist.as_deref(), Some("Artist"));
+        assert_eq!(metadata.album, None);
+        assert_eq!(metadata.cover.as_deref(), Some(&[2][..]));
+    }
+}
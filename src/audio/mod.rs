@@ -1,7 +1,8 @@
 mod file;
+mod metadata;
 mod player;
 
-pub use file::AudioFile;
+pub use file::{save_wav, AudioFile, TrackInfo};
 pub use player::AudioPlayer;
 
 enum Sample<S> {
@@ -18,6 +19,8 @@ pub enum CopyMethod {
 #[derive(Debug)]
 pub struct PlaybackPosition {
     pub instant: instant::Instant,
+    /// Time in the file that playback started from
+    pub music_start: f64,
     pub music_position: f64,
     pub music_length: f64,
 }
@@ -26,8 +29,23 @@ impl Default for PlaybackPosition {
     fn default() -> Self {
         Self {
             instant: instant::Instant::now(),
+            music_start: 0.0,
             music_position: 0.0,
             music_length: 0.0,
         }
     }
 }
+
+impl PlaybackPosition {
+    /// Time in the file extrapolated from the last callback to now.
+    pub fn position_now(&self) -> f64 {
+        let now = instant::Instant::now();
+        let diff = if now > self.instant {
+            (now - self.instant).as_secs_f64()
+        } else {
+            -(self.instant - now).as_secs_f64()
+        };
+
+        self.music_start + self.music_position + diff
+    }
+}
@@ -1,22 +1,27 @@
 use std::{
-    cell::Cell,
-    path::PathBuf,
+    cell::{Cell, RefCell},
     sync::{mpsc, Arc, Mutex},
 };
 
-use anyhow::{Error, Result};
+use anyhow::Result;
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     FromSample, SizedSample,
 };
 use num::traits::Zero;
 
-use crate::Cli;
+use crate::{dsp::resample::Resampler, error::PhonolyzeError, Cli};
 
 use super::{AudioFile, CopyMethod, PlaybackPosition, Sample};
 
+/// Song, time to start from and track for the decoder thread.  Without a
+/// time, playback carries on from where the previous song got to.
+type PlayRequest = (String, Option<f64>, Option<usize>);
+
 pub struct AudioPlayer {
-    tx_play_song: mpsc::Sender<PathBuf>,
+    tx_play_song: mpsc::Sender<PlayRequest>,
+    /// Song last asked for
+    song: RefCell<Option<String>>,
     pub progress: Arc<Mutex<PlaybackPosition>>,
     _stream: cpal::Stream,
 }
@@ -25,6 +30,7 @@ impl std::fmt::Debug for AudioPlayer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AudioPlayer")
             .field("tx_play_song", &self.tx_play_song)
+            .field("song", &self.song)
             .field("progress", &self.progress)
             .field("_stream", &"n/a")
             .finish()
@@ -47,7 +53,7 @@ impl AudioPlayer {
         let latency_samples = (latency_frames * channels as u32) as usize;
         let (mut txrb_audio, mut rxrb_audio) =
             rtrb::RingBuffer::<Sample<S>>::new(latency_samples * 2);
-        let (tx_play_song, rx_play_song) = mpsc::channel::<PathBuf>();
+        let (tx_play_song, rx_play_song) = mpsc::channel::<PlayRequest>();
         //let (tx_stop_song, rx_stop_song) = mpsc::sync_channel::<()>(1);
         dbg!("output device", sample_rate, channels, latency_frames, latency_samples);
 
@@ -58,37 +64,100 @@ impl AudioPlayer {
 
         std::thread::spawn(move || {
             pollster::block_on(async {
-                while let Ok(song) = rx_play_song.recv() {
-                    let mut audio = AudioFile::open(song.to_str().unwrap()).await.unwrap();
-                    txrb_audio
-                        .push(Sample::SetChannels(audio.channels()))
-                        .unwrap();
+                let mut request = rx_play_song.recv().ok();
+                // Time in the file of the next frame to be queued
+                let mut position = 0.;
+
+                while let Some((song, start, track)) = request.take() {
+                    let start = start.unwrap_or(position);
+                    let mut audio = match AudioFile::open_track(&song, track).await {
+                        Ok(audio) => audio,
+                        Err(e) => {
+                            log::error!("Failed to play {song}: {e}");
+                            request = rx_play_song.recv().ok();
+                            continue;
+                        }
+                    };
+                    let mut skip = 0;
+
+                    // Playing from the beginning instead would put the
+                    // cursor in the wrong place.
+                    if start > 0. {
+                        match audio.seek(start) {
+                            Ok(frames) => skip = frames * audio.channels(),
+                            Err(e) => {
+                                log::error!("Failed to seek to {start} s: {e}");
+                                request = rx_play_song.recv().ok();
+                                continue;
+                            }
+                        }
+                    }
+                    position = start;
+
+                    // Wait for room while the buffer is full.
+                    let mut send = |sample: Sample<S>| {
+                        while txrb_audio.is_full() {
+                            std::thread::sleep(instant::Duration::from_millis(
+                                latency_ms as u64 / 2,
+                            ));
+                        }
+                        let _ = txrb_audio.push(sample);
+                    };
+
+                    // Files at another rate than the device would play at
+                    // the wrong pitch.
+                    let mut layout = (audio.sample_rate(), audio.channels());
+                    let mut resampler = Resampler::new(layout.0, sample_rate as u32, layout.1);
+
+                    send(Sample::SetChannels(layout.1));
+
                     loop {
+                        // A newer request takes over straight away.
+                        if let Ok(next) = rx_play_song.try_recv() {
+                            request = Some(next);
+                            break;
+                        }
+
                         match audio.next_sample(CopyMethod::Interleaved) {
                             Ok(Some(signal)) => {
+                                // A decoder reset can change the rate or the
+                                // channels of the rest of the stream.
+                                if (audio.sample_rate(), audio.channels()) != layout {
+                                    for sample in resampler.flush() {
+                                        send(Sample::Signal(S::from_sample(sample)));
+                                    }
+                                    layout = (audio.sample_rate(), audio.channels());
+                                    resampler =
+                                        Resampler::new(layout.0, sample_rate as u32, layout.1);
+                                    send(Sample::SetChannels(layout.1));
+                                }
+
                                 let samples = signal.samples();
+                                let skipped = skip.min(samples.len());
+                                let frames = (samples.len() - skipped) / layout.1.max(1);
 
-                                for sample in samples {
-                                    loop {
-                                        if txrb_audio
-                                            .push(Sample::Signal(S::from_sample(*sample)))
-                                            .is_ok()
-                                        {
-                                            break;
-                                        }
-                                        std::thread::sleep(instant::Duration::from_millis(
-                                            latency_ms as u64 / 2,
-                                        ));
-                                    }
+                                skip -= skipped;
+                                position += frames as f64 / layout.0 as f64;
+                                for sample in resampler.process(&samples[skipped..]) {
+                                    send(Sample::Signal(S::from_sample(sample)));
+                                }
+                            }
+                            Ok(None) => {
+                                for sample in resampler.flush() {
+                                    send(Sample::Signal(S::from_sample(sample)));
                                 }
+                                break;
                             }
-                            Ok(None) => break,
                             Err(e) => {
                                 log::error!("{e:?}");
                                 break;
                             }
                         }
                     }
+
+                    if request.is_none() {
+                        request = rx_play_song.recv().ok();
+                    }
                 }
             });
         });
@@ -183,46 +252,69 @@ impl AudioPlayer {
         Ok(AudioPlayer {
             _stream,
             tx_play_song,
+            song: RefCell::new(None),
             progress,
             //sample_count,
         })
     }
 
-    pub fn play(&self, song: std::path::PathBuf) {
-        self.tx_play_song.send(song).unwrap();
+    /// Play a track of a file, or its default track, from a time in seconds.
+    pub fn play(&self, song: &str, start: f64, track: Option<usize>) -> crate::error::Result<()> {
+        self.song.replace(Some(song.to_string()));
+        self.request((song.to_string(), Some(start), track))
+    }
+
+    /// Carry on playing the song on another of its tracks, if one has
+    /// been played.
+    pub fn switch_track(&self, track: usize) -> crate::error::Result<()> {
+        match self.song.borrow().clone() {
+            Some(song) => self.request((song, None, Some(track))),
+            None => Ok(()),
+        }
+    }
+
+    fn request(&self, request: PlayRequest) -> crate::error::Result<()> {
+        self.tx_play_song
+            .send(request)
+            .map_err(|_| PhonolyzeError::AudioOutput("the decoder thread stopped".to_string()))
     }
 }
 
-impl From<&Cli> for AudioPlayer {
-    fn from(cli: &Cli) -> Self {
+impl TryFrom<&Cli> for AudioPlayer {
+    type Error = PhonolyzeError;
+
+    fn try_from(cli: &Cli) -> crate::error::Result<Self> {
         let device = cpal::default_host()
             .default_output_device()
-            .ok_or(Error::msg("No audio device found"))
-            .unwrap();
-        let config = device.default_output_config().unwrap();
-        let audio_player = {
-            match config.sample_format() {
-                cpal::SampleFormat::I8 => pollster::block_on(AudioPlayer::new::<i8>(
-                    &device,
-                    &config.into(),
-                    cli.latency_ms,
-                    cli.chunk_size,
-                )),
-                cpal::SampleFormat::F32 => pollster::block_on(AudioPlayer::new::<f32>(
-                    &device,
-                    &config.into(),
-                    cli.latency_ms,
-                    cli.chunk_size,
-                )),
-                _ => panic!("unsupported format"),
+            .ok_or_else(|| PhonolyzeError::AudioOutput("no output device".to_string()))?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| PhonolyzeError::AudioOutput(e.to_string()))?;
+        let audio_player = match config.sample_format() {
+            cpal::SampleFormat::I8 => pollster::block_on(AudioPlayer::new::<i8>(
+                &device,
+                &config.into(),
+                cli.latency_ms,
+                cli.chunk_size,
+            )),
+            cpal::SampleFormat::F32 => pollster::block_on(AudioPlayer::new::<f32>(
+                &device,
+                &config.into(),
+                cli.latency_ms,
+                cli.chunk_size,
+            )),
+            format => {
+                return Err(PhonolyzeError::AudioOutput(format!(
+                    "unsupported sample format {format}"
+                )))
             }
-            .unwrap()
-        };
+        }
+        .map_err(|e| PhonolyzeError::AudioOutput(e.to_string()))?;
 
         if cli.play_audio {
-            audio_player.play(cli.audio_file.clone().into());
+            audio_player.play(&cli.audio_file, cli.start, cli.track.first().copied())?;
         };
 
-        audio_player
+        Ok(audio_player)
     }
 }
@@ -0,0 +1,162 @@
+//! Voice activity detection and a speech/music classifier per STFT frame.
+use strum_macros::Display;
+
+use super::StftGrid;
+
+/// Rise above the quiet frames needed to count as active.
+const ACTIVE_DB: f32 = 10.;
+
+/// Normalized spectral entropy above which a frame sounds like noise.
+const MAX_ENTROPY: f32 = 0.85;
+
+/// Zero crossings per sample above which unvoiced speech is assumed.
+const UNVOICED_ZCR: f32 = 0.1;
+
+/// Frames on either side considered when smoothing and classifying.
+const CONTEXT_SECONDS: f64 = 0.5;
+
+/// Speech alternates between syllables and short pauses, so more of its
+/// frames fall well below the average energy than in music.
+const SPEECH_LOW_ENERGY: f32 = 0.25;
+
+/// Variation of the zero crossing rate typical of speech.
+const SPEECH_ZCR_VARIATION: f32 = 0.6;
+
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum Activity {
+    Inactive,
+    Speech,
+    Music,
+}
+
+/// Per-frame descriptors.
+#[derive(Copy, Clone, Debug)]
+struct Features {
+    energy: f32,
+    entropy: f32,
+    zcr: f32,
+}
+
+/// Classify each STFT frame of a signal.
+pub fn classify(signal: &[f32], frames: &[Vec<f32>], grid: &StftGrid) -> Vec<Activity> {
+    let features: Vec<Features> = frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let start = (i * grid.hop_size).min(signal.len());
+            let end = (start + grid.window_size).min(signal.len());
+
+            features(frame, &signal[start..end])
+        })
+        .collect();
+    let context =
+        ((CONTEXT_SECONDS * grid.sample_rate as f64) / grid.hop_size as f64).round() as usize;
+    let mut energies: Vec<f32> = features
+        .iter()
+        .map(|f| f.energy)
+        .filter(|energy| energy.is_finite())
+        .collect();
+    let quiet = if energies.is_empty() {
+        0.
+    } else {
+        let index = energies.len() / 10;
+        *energies
+            .select_nth_unstable_by(index, |a, b| a.total_cmp(b))
+            .1
+    };
+
+    let active: Vec<bool> = features
+        .iter()
+        .map(|f| f.energy > quiet + ACTIVE_DB && (f.entropy < MAX_ENTROPY || f.zcr > UNVOICED_ZCR))
+        .collect();
+
+    (0..features.len())
+        .map(|i| {
+            let range = i.saturating_sub(context)..(i + context + 1).min(features.len());
+            let nearby = &features[range.clone()];
+            let votes = active[range].iter().filter(|a| **a).count();
+
+            if votes * 2 <= nearby.len() {
+                Activity::Inactive
+            } else if is_speech(nearby) {
+                Activity::Speech
+            } else {
+                Activity::Music
+            }
+        })
+        .collect()
+}
+
+fn features(frame: &[f32], samples: &[f32]) -> Features {
+    let power: Vec<f32> = frame.iter().map(|level| 10f32.powf(level / 10.)).collect();
+    let total: f32 = power.iter().sum();
+    let entropy = if total > 0. {
+        -power
+            .iter()
+            .map(|p| p / total)
+            .filter(|p| *p > 0.)
+            .map(|p| p * p.ln())
+            .sum::<f32>()
+            / (power.len() as f32).ln()
+    } else {
+        1.
+    };
+    let crossings = samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.) != (pair[1] >= 0.))
+        .count();
+
+    Features {
+        energy: 10. * total.log10(),
+        entropy,
+        zcr: crossings as f32 / samples.len().max(1) as f32,
+    }
+}
+
+/// Low energy frame ratio and zero crossing rate variation, after Scheirer
+/// and Slaney.
+fn is_speech(nearby: &[Features]) -> bool {
+    let n = nearby.len() as f32;
+    let power: Vec<f32> = nearby.iter().map(|f| 10f32.powf(f.energy / 10.)).collect();
+    let mean_power = power.iter().sum::<f32>() / n;
+    let low_energy = power.iter().filter(|p| **p < 0.5 * mean_power).count() as f32 / n;
+    let mean_zcr = nearby.iter().map(|f| f.zcr).sum::<f32>() / n;
+    let zcr_deviation = (nearby
+        .iter()
+        .map(|f| (f.zcr - mean_zcr).powi(2))
+        .sum::<f32>()
+        / n)
+        .sqrt();
+
+    low_energy > SPEECH_LOW_ENERGY || zcr_deviation > SPEECH_ZCR_VARIATION * mean_zcr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::white_noise;
+
+    #[test]
+    fn silence_then_tone() {
+        let grid = StftGrid {
+            sample_rate: 16000,
+            window_size: 512,
+            hop_size: 256,
+            offset: 0.,
+        };
+        let mut noise = white_noise(1);
+        // Two seconds of faint hiss, then two of a steady tone.
+        let signal: Vec<f32> = (0..64000)
+            .map(|i| {
+                let tone = 0.5 * (2. * std::f32::consts::PI * 440. * i as f32 / 16000.).sin();
+                1e-4 * noise() + if i < 32000 { 0. } else { tone }
+            })
+            .collect();
+        let (frames, _) = crate::fft::stft(&signal, "hamming", 512, 256);
+        let activity = classify(&signal, &frames, &grid);
+        let second = |seconds: f64| activity[grid.seconds_frame(seconds)];
+
+        assert_eq!(second(1.), Activity::Inactive);
+        assert_eq!(second(3.), Activity::Music);
+    }
+}
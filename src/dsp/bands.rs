@@ -0,0 +1,260 @@
+//! Fractional-octave bands following the base-ten IEC 61260 series.
+use std::io::{self, Write};
+
+use strum_macros::{Display, EnumIter};
+
+use super::{weighting::Weighting, StftGrid};
+use crate::{fft::get_window, timecode::Timecode};
+
+/// Octave ratio of the base-ten system.
+const G: f32 = 1.995_262_3;
+
+/// Preferred numbers for labelling octave and third-octave bands.
+const R10: [f32; 10] = [1.0, 1.25, 1.6, 2.0, 2.5, 3.15, 4.0, 5.0, 6.3, 8.0];
+
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq, clap::ValueEnum)]
+pub enum Fraction {
+    #[strum(serialize = "1/1 octave")]
+    Octave,
+    #[default]
+    #[strum(serialize = "1/3 octave")]
+    Third,
+    #[strum(serialize = "1/6 octave")]
+    Sixth,
+}
+
+impl Fraction {
+    pub fn bands_per_octave(&self) -> i32 {
+        match self {
+            Self::Octave => 1,
+            Self::Third => 3,
+            Self::Sixth => 6,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Band {
+    /// Exact mid-band frequency
+    pub center: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+impl Band {
+    /// Nominal mid-band frequency for labels.
+    pub fn nominal(&self) -> f32 {
+        let decade = 10f32.powf(self.center.log10().floor());
+        let preferred = R10
+            .iter()
+            .chain(&[10.])
+            .map(|r| r * decade)
+            .min_by(|a, b| (a - self.center).abs().total_cmp(&(b - self.center).abs()))
+            .unwrap();
+
+        if (preferred / self.center - 1.).abs() < 0.03 {
+            preferred
+        } else {
+            let scale = 10f32.powf(self.center.log10().floor() - 2.);
+            (self.center / scale).round() * scale
+        }
+    }
+
+    /// Nominal mid-band frequency in Hz as text.
+    pub fn label(&self) -> String {
+        let nominal = self.nominal();
+
+        if nominal < 100. {
+            format!("{nominal:.1}")
+        } else {
+            format!("{nominal:.0}")
+        }
+    }
+}
+
+/// Bands with mid-band frequencies from 20 Hz up to 20 kHz or Nyquist.
+pub fn bands(fraction: Fraction, sample_rate: u32) -> Vec<Band> {
+    let b = fraction.bands_per_octave();
+    let max_hz = (sample_rate as f32 / 2.).min(20000.);
+
+    (-40 * b..=20 * b)
+        .map(|x| {
+            let exponent = if b % 2 == 1 {
+                x as f32 / b as f32
+            } else {
+                (2 * x + 1) as f32 / (2 * b) as f32
+            };
+            let center = 1000. * G.powf(exponent);
+            let half = G.powf(1. / (2 * b) as f32);
+
+            Band {
+                center,
+                low: center / half,
+                high: center * half,
+            }
+        })
+        .filter(|band| band.center >= 20. / 1.01 && band.center <= max_hz * 1.01)
+        .collect()
+}
+
+/// Aggregates STFT bins into band levels.
+///
+/// Bins contribute in proportion to how much of their width overlaps a band,
+/// and levels are in dB relative to a full-scale sine.
+#[derive(Clone, Debug)]
+pub struct BandFilter {
+    weights: Vec<Vec<(usize, f32)>>,
+    mean_square: f32,
+}
+
+impl BandFilter {
+    pub fn new(bands: &[Band], grid: &StftGrid) -> Self {
+        let bin_width = grid.bin_hz(1.);
+        let window = get_window("hamming", grid.window_size);
+        let mean_square = window.iter().map(|w| w * w).sum::<f32>() / window.len() as f32;
+        let weights = bands
+            .iter()
+            .map(|band| {
+                let first = ((band.low / bin_width - 0.5).floor().max(0.)) as usize;
+                let last = (band.high / bin_width + 0.5).ceil() as usize;
+
+                (first..=last)
+                    .filter_map(|bin| {
+                        let center = grid.bin_hz(bin as f32);
+                        let overlap = (center + bin_width / 2.).min(band.high)
+                            - (center - bin_width / 2.).max(band.low);
+
+                        (overlap > 0.).then_some((bin, overlap / bin_width))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        BandFilter {
+            weights,
+            mean_square,
+        }
+    }
+
+    /// Band levels of one frame, with per-bin weighting gains in dB.
+    pub fn levels(&self, frame: &[f32], gains: &[f32]) -> Vec<f32> {
+        self.weights
+            .iter()
+            .map(|bins| {
+                let power: f32 = bins
+                    .iter()
+                    .filter(|(bin, _)| *bin < frame.len())
+                    .map(|(bin, weight)| {
+                        let gain = gains.get(*bin).copied().unwrap_or(0.);
+                        weight * 10f32.powf((frame[*bin] + gain) / 10.)
+                    })
+                    .sum();
+
+                10. * (power / self.mean_square).log10()
+            })
+            .collect()
+    }
+}
+
+/// Levels of each band in each frame.
+pub fn band_levels(
+    frames: &[Vec<f32>],
+    bands: &[Band],
+    grid: &StftGrid,
+    weighting: Weighting,
+) -> Vec<Vec<f32>> {
+    let filter = BandFilter::new(bands, grid);
+    let gains = weighting.gains(grid);
+
+    frames
+        .iter()
+        .map(|frame| filter.levels(frame, &gains))
+        .collect()
+}
+
+/// Write band levels over time with one column per band.
+pub fn write_csv(
+    levels: &[Vec<f32>],
+    bands: &[Band],
+    grid: &StftGrid,
+    timecode: &Timecode,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    write!(writer, "{}", timecode.header())?;
+    for band in bands {
+        write!(writer, ",{}", band.label())?;
+    }
+    writeln!(writer)?;
+
+    for (frame, levels) in levels.iter().enumerate() {
+        let time = timecode.format_export(grid.frame_seconds(frame as f32));
+        write!(writer, "{time}")?;
+        for level in levels {
+            write!(writer, ",{:.2}", level.max(-200.))?;
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn third_octave_centers() {
+        let bands = bands(Fraction::Third, 48000);
+        let nominal: Vec<f32> = bands.iter().map(|b| b.nominal()).collect();
+
+        assert_eq!(bands.len(), 31);
+        assert_eq!(nominal[0], 20.);
+        assert!(nominal.contains(&31.5));
+        assert!(nominal.contains(&1000.));
+        assert_eq!(nominal[30], 20000.);
+    }
+
+    #[test]
+    fn full_scale_sine() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 4096,
+            hop_size: 4096,
+            offset: 0.,
+        };
+        let signal: Vec<f32> = (0..8192)
+            .map(|i| (2. * std::f32::consts::PI * 1000. * i as f32 / 48000.).sin())
+            .collect();
+        let (frames, _) = crate::fft::stft(&signal, "hamming", 4096, 4096);
+        let octaves = bands(Fraction::Octave, 48000);
+        let levels = band_levels(&frames, &octaves, &grid, Weighting::Z);
+        let khz = octaves.iter().position(|b| b.nominal() == 1000.).unwrap();
+
+        assert!(levels[0][khz].abs() < 0.5, "{}", levels[0][khz]);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 1024,
+            hop_size: 480,
+            offset: 0.,
+        };
+        let octaves = bands(Fraction::Octave, 48000);
+        let mut csv = Vec::new();
+        write_csv(
+            &[vec![-3., -250.]],
+            &octaves[..2],
+            &grid,
+            &Timecode::default(),
+            &mut csv,
+        )
+        .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("time_s,31.5,63.0"));
+        assert_eq!(lines.next(), Some("0.010667,-3.00,-200.00"));
+    }
+}
@@ -0,0 +1,297 @@
+//! Room acoustic parameters from the Schroeder decay of an impulse response
+//! (ISO 3382).
+use std::io::{self, Write};
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::bands::Band;
+
+/// Order of the Butterworth magnitude response of the band filters.
+const FILTER_ORDER: i32 = 3;
+/// The direct sound arrives where the response first comes this many dB
+/// below its peak.
+const ONSET_DB: f32 = 20.;
+/// Spacing of the points of the decay curves.
+const CURVE_STEP_SECONDS: f32 = 0.001;
+/// Blocks the squared response is averaged over to find the noise floor.
+const BLOCK_SECONDS: f32 = 0.01;
+/// Most refinements of the noise floor and truncation point.
+const LUNDEBY_ITERATIONS: usize = 5;
+
+/// Backward-integrated energy decay within one band.
+#[derive(Clone, Debug)]
+pub struct Decay {
+    pub band: Band,
+    /// Decay in dB relative to the total energy from the arrival of the
+    /// direct sound to where the response sinks into the noise
+    pub curve: Vec<f32>,
+    /// Seconds between curve points
+    pub step_seconds: f32,
+    /// Early decay time from the 0 to -10 dB range
+    pub edt: Option<f32>,
+    /// Decay time from the -5 to -25 dB range
+    pub t20: Option<f32>,
+    /// Decay time from the -5 to -35 dB range
+    pub t30: Option<f32>,
+    /// Clarity for speech in dB
+    pub c50: f32,
+    /// Clarity for music in dB
+    pub c80: f32,
+    /// Definition as the early fraction of the energy
+    pub d50: f32,
+}
+
+impl Decay {
+    /// Reverberation time, from the widest evaluation range reached.
+    pub fn rt60(&self) -> Option<f32> {
+        self.t30.or(self.t20).or(self.edt)
+    }
+}
+
+/// Decay of each band of an impulse response, filtered sample by sample
+/// and measured from the arrival of the direct sound.
+pub fn decays(signal: &[f32], sample_rate: u32, bands: &[Band]) -> Vec<Decay> {
+    let onset = onset(signal);
+    // Room for the filters to ring without wrapping around.
+    let size = (signal.len() + sample_rate as usize / 10).next_power_of_two();
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+    let mut spectrum: Vec<Complex<f32>> =
+        signal.iter().map(|x| Complex { re: *x, im: 0. }).collect();
+    spectrum.resize(size, Complex { re: 0., im: 0. });
+    forward.process(&mut spectrum);
+
+    bands
+        .iter()
+        .map(|band| {
+            let mut filtered: Vec<Complex<f32>> = spectrum
+                .iter()
+                .enumerate()
+                .map(|(bin, x)| {
+                    let hz = bin.min(size - bin) as f32 * sample_rate as f32 / size as f32;
+                    x * band_gain(band, hz)
+                })
+                .collect();
+            inverse.process(&mut filtered);
+
+            let energy: Vec<f32> = filtered[onset..signal.len()]
+                .iter()
+                .map(|x| (x.re / size as f32).powi(2))
+                .collect();
+
+            decay(*band, &energy, sample_rate)
+        })
+        .collect()
+}
+
+/// Zero-phase gain of a Butterworth band-pass with its -3 dB points at the
+/// band edges.
+fn band_gain(band: &Band, hz: f32) -> f32 {
+    if hz <= 0. {
+        return 0.;
+    }
+
+    let edge = band.high / band.center;
+    let x = (hz / band.center - band.center / hz) / (edge - 1. / edge);
+
+    1. / (1. + x.powi(2 * FILTER_ORDER)).sqrt()
+}
+
+/// First sample within `ONSET_DB` of the peak.
+fn onset(signal: &[f32]) -> usize {
+    let peak = signal.iter().fold(0f32, |peak, x| peak.max(x * x));
+    let threshold = peak * 10f32.powf(-ONSET_DB / 10.);
+
+    signal.iter().position(|x| x * x >= threshold).unwrap_or(0)
+}
+
+/// Parameters of one band from its squared response.
+fn decay(band: Band, energy: &[f32], sample_rate: u32) -> Decay {
+    let (end, tail) = lundeby(energy, sample_rate);
+    let energy = &energy[..end];
+    let total = energy.iter().map(|e| *e as f64).sum::<f64>() + tail;
+    let early = |seconds: f32| {
+        energy
+            .iter()
+            .take((seconds * sample_rate as f32).round() as usize)
+            .map(|e| *e as f64)
+            .sum::<f64>()
+    };
+    let clarity = |seconds| (10. * (early(seconds) / (total - early(seconds))).log10()) as f32;
+    let step = ((CURVE_STEP_SECONDS * sample_rate as f32).round() as usize).max(1);
+    let step_seconds = step as f32 / sample_rate as f32;
+    let curve = schroeder(energy, tail, step);
+
+    Decay {
+        band,
+        edt: decay_time(&curve, step_seconds, 0., -10.),
+        t20: decay_time(&curve, step_seconds, -5., -25.),
+        t30: decay_time(&curve, step_seconds, -5., -35.),
+        c50: clarity(0.05),
+        c80: clarity(0.08),
+        d50: (early(0.05) / total) as f32,
+        curve,
+        step_seconds,
+    }
+}
+
+/// Where the decay sinks into the noise, after Lundeby et al. (1995), and
+/// the energy the decay would have had beyond that point.
+fn lundeby(energy: &[f32], sample_rate: u32) -> (usize, f64) {
+    let fs = sample_rate as f32;
+    let block = ((BLOCK_SECONDS * fs) as usize).max(1);
+    let levels: Vec<f32> = energy.chunks(block).map(mean_db).collect();
+    let last_tenth = energy.len() * 9 / 10;
+    let mut noise = mean_db(&energy[last_tenth..]);
+    let mut fit = None;
+    let mut crossing = energy.len();
+
+    for _ in 0..LUNDEBY_ITERATIONS {
+        // Fit the decay down to 10 dB above the noise, then see where the
+        // line meets the noise.
+        let end = levels
+            .iter()
+            .position(|level| *level <= noise + 10.)
+            .unwrap_or(levels.len());
+        let points: Vec<(f32, f32)> = levels[..end]
+            .iter()
+            .enumerate()
+            .map(|(i, level)| ((i as f32 + 0.5) * block as f32 / fs, *level))
+            .filter(|(_, level)| level.is_finite())
+            .collect();
+        let Some((intercept, slope)) = line_fit(&points).filter(|(_, slope)| *slope < 0.) else {
+            break;
+        };
+        let at = |db: f32| ((db - intercept) / slope * fs).max(0.) as usize;
+        let next = at(noise).clamp(1, energy.len());
+
+        // The noise is measured again from 10 dB further down the line.
+        fit = Some((intercept, slope));
+        noise = mean_db(&energy[at(noise - 10.).min(last_tenth)..]);
+        if next == crossing {
+            break;
+        }
+        crossing = next;
+    }
+
+    let Some((intercept, slope)) = fit else {
+        return (energy.len(), 0.);
+    };
+    let power = 10f64.powf((intercept + slope * crossing as f32 / fs) as f64 / 10.);
+    let tail = power * fs as f64 * 10. / (-slope as f64 * std::f64::consts::LN_10);
+
+    (crossing, tail)
+}
+
+fn mean_db(energy: &[f32]) -> f32 {
+    10. * (energy.iter().sum::<f32>() / energy.len().max(1) as f32).log10()
+}
+
+/// Remaining energy every `step` samples in dB relative to the total,
+/// including the energy beyond the end.
+fn schroeder(energy: &[f32], tail: f64, step: usize) -> Vec<f32> {
+    let mut remaining: Vec<f64> = energy
+        .iter()
+        .rev()
+        .scan(tail, |sum, e| {
+            *sum += *e as f64;
+            Some(*sum)
+        })
+        .collect();
+    remaining.reverse();
+
+    let total = remaining.first().copied().unwrap_or(0.);
+    remaining
+        .iter()
+        .step_by(step)
+        .map(|e| (10. * (e / total).log10()) as f32)
+        .collect()
+}
+
+/// Intercept and slope of the least squares line through some points.
+fn line_fit(points: &[(f32, f32)]) -> Option<(f32, f32)> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f32;
+    let (sx, sy) = points
+        .iter()
+        .fold((0., 0.), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (mx, my) = (sx / n, sy / n);
+    let (sxy, sxx) = points.iter().fold((0., 0.), |(sxy, sxx), (x, y)| {
+        (sxy + (x - mx) * (y - my), sxx + (x - mx).powi(2))
+    });
+    let slope = sxy / sxx;
+
+    Some((my - slope * mx, slope))
+}
+
+/// Time to decay by 60 dB, extrapolated from a least squares fit of the
+/// curve between two levels.  None when the curve doesn't reach `to`.
+fn decay_time(curve: &[f32], step_seconds: f32, from: f32, to: f32) -> Option<f32> {
+    let end = curve.iter().position(|level| *level <= to)?;
+    let points: Vec<(f32, f32)> = curve[..=end]
+        .iter()
+        .enumerate()
+        .filter(|(_, level)| **level <= from)
+        .map(|(step, level)| (step as f32 * step_seconds, *level))
+        .collect();
+    let (_, slope) = line_fit(&points)?;
+
+    (slope < 0.).then(|| -60. / slope)
+}
+
+/// Write one row of parameters per band.
+pub fn write_csv(decays: &[Decay], mut writer: impl Write) -> io::Result<()> {
+    let seconds = |time: Option<f32>| time.map(|t| format!("{t:.3}")).unwrap_or_default();
+
+    writeln!(writer, "band_hz,edt_s,t20_s,t30_s,rt60_s,c50_db,c80_db,d50")?;
+    for decay in decays {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{:.2},{:.2},{:.3}",
+            decay.band.label(),
+            seconds(decay.edt),
+            seconds(decay.t20),
+            seconds(decay.t30),
+            seconds(decay.rt60()),
+            decay.c50,
+            decay.c80,
+            decay.d50,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::{
+        bands::{bands, Fraction},
+        white_noise,
+    };
+
+    #[test]
+    fn exponential_decay_over_noise() {
+        let rt60 = 0.5;
+        let mut noise = white_noise(1);
+        let signal: Vec<f32> = (0..72000)
+            .map(|i| {
+                let t = i as f32 / 48000.;
+                noise() * 10f32.powf(-3. * t / rt60) + 0.003 * noise()
+            })
+            .collect();
+        let octaves = bands(Fraction::Octave, 48000);
+        let decays = decays(&signal, 48000, &octaves);
+        let khz = decays.iter().find(|d| d.band.nominal() == 1000.).unwrap();
+        let t30 = khz.t30.unwrap();
+        // Energy decaying as exp(-6 ln 10 t / rt60) has this much before 50 ms.
+        let early = 1. - 10f32.powf(-6. * 0.05 / rt60);
+
+        assert!((t30 - rt60).abs() < 0.05, "{t30}");
+        assert!((khz.d50 - early).abs() < 0.05, "{}", khz.d50);
+    }
+}
@@ -0,0 +1,211 @@
+//! Intake checks for clipping, DC offset, dropouts and clicks.
+use strum_macros::Display;
+
+/// Magnitude treated as full scale.
+const CLIP_LEVEL: f32 = 0.999;
+
+/// Consecutive full-scale samples that count as clipping.
+const MIN_CLIP_RUN: usize = 3;
+
+/// Length of the blocks checked for DC offset.
+const DC_SECONDS: f32 = 1.;
+
+/// Block mean above which DC offset is reported, -40 dBFS.
+const MAX_DC: f32 = 0.01;
+
+/// Shortest run of digital silence reported as a dropout.
+const MIN_DROPOUT_SECONDS: f32 = 0.01;
+
+/// Length of the blocks that clicks are compared against.
+const CLICK_BLOCK: usize = 1024;
+
+/// Ratio of a second difference to its block RMS that counts as a click.
+const CLICK_RATIO: f32 = 10.;
+
+/// Smallest second difference reported as a click.
+const MIN_CLICK: f32 = 0.05;
+
+/// Clicks closer than this are merged.
+const CLICK_GAP_SECONDS: f32 = 0.01;
+
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum DefectKind {
+    Clipping,
+    #[strum(serialize = "DC offset")]
+    DcOffset,
+    Dropout,
+    Click,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Defect {
+    pub kind: DefectKind,
+    /// First sample
+    pub start: usize,
+    /// Sample after the last
+    pub end: usize,
+    /// Peak magnitude for clipping and clicks, mean for DC offset
+    pub value: f32,
+}
+
+/// All findings, ordered by start.
+pub fn detect(signal: &[f32], sample_rate: u32) -> Vec<Defect> {
+    let rate = sample_rate as f32;
+    let mut defects = clipping(signal);
+
+    defects.extend(dc_offset(signal, (DC_SECONDS * rate) as usize));
+    defects.extend(dropouts(signal, (MIN_DROPOUT_SECONDS * rate) as usize));
+    defects.extend(clicks(signal, (CLICK_GAP_SECONDS * rate) as usize));
+    defects.sort_by_key(|defect| defect.start);
+    defects
+}
+
+/// Runs of samples matching a predicate that are at least `min_len` long.
+fn runs(signal: &[f32], min_len: usize, matches: impl Fn(f32) -> bool) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+
+    for (i, x) in signal.iter().chain(&[f32::NAN]).enumerate() {
+        match (start, matches(*x) && i < signal.len()) {
+            (None, true) => start = Some(i),
+            (Some(first), false) => {
+                if i - first >= min_len {
+                    runs.push((first, i));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    runs
+}
+
+fn clipping(signal: &[f32]) -> Vec<Defect> {
+    let positive = runs(signal, MIN_CLIP_RUN, |x| x >= CLIP_LEVEL);
+    let negative = runs(signal, MIN_CLIP_RUN, |x| x <= -CLIP_LEVEL);
+
+    positive
+        .into_iter()
+        .chain(negative)
+        .map(|(start, end)| Defect {
+            kind: DefectKind::Clipping,
+            start,
+            end,
+            value: signal[start].abs(),
+        })
+        .collect()
+}
+
+fn dc_offset(signal: &[f32], block: usize) -> Vec<Defect> {
+    let mut defects: Vec<Defect> = Vec::new();
+
+    for (i, chunk) in signal.chunks(block.max(1)).enumerate() {
+        let sum = chunk.iter().sum::<f32>();
+        let mean = sum / chunk.len() as f32;
+
+        if mean.abs() <= MAX_DC {
+            continue;
+        }
+
+        let start = i * block;
+        match defects.last_mut() {
+            Some(last) if last.end == start => {
+                let before = (start - last.start) as f32;
+
+                last.end = start + chunk.len();
+                last.value = (last.value * before + sum) / (last.end - last.start) as f32;
+            }
+            _ => defects.push(Defect {
+                kind: DefectKind::DcOffset,
+                start,
+                end: start + chunk.len(),
+                value: mean,
+            }),
+        }
+    }
+
+    defects
+}
+
+/// Digital silence surrounded by signal, so that leading and trailing
+/// silence isn't reported.
+fn dropouts(signal: &[f32], min_len: usize) -> Vec<Defect> {
+    runs(signal, min_len.max(1), |x| x == 0.)
+        .into_iter()
+        .filter(|(start, end)| *start > 0 && *end < signal.len())
+        .map(|(start, end)| Defect {
+            kind: DefectKind::Dropout,
+            start,
+            end,
+            value: 0.,
+        })
+        .collect()
+}
+
+/// Second differences far above the level of their surroundings.
+fn clicks(signal: &[f32], gap: usize) -> Vec<Defect> {
+    let difference: Vec<f32> = signal.windows(3).map(|x| x[2] - 2. * x[1] + x[0]).collect();
+    let mut defects: Vec<Defect> = Vec::new();
+
+    for (i, chunk) in difference.chunks(CLICK_BLOCK).enumerate() {
+        let rms = (chunk.iter().map(|d| d * d).sum::<f32>() / chunk.len() as f32).sqrt();
+        let threshold = (rms * CLICK_RATIO).max(MIN_CLICK);
+
+        for (j, d) in chunk.iter().enumerate() {
+            if d.abs() <= threshold {
+                continue;
+            }
+
+            let sample = i * CLICK_BLOCK + j + 1;
+            match defects.last_mut() {
+                Some(last) if sample <= last.end + gap => {
+                    last.end = sample + 1;
+                    last.value = last.value.max(d.abs());
+                }
+                _ => defects.push(Defect {
+                    kind: DefectKind::Click,
+                    start: sample,
+                    end: sample + 1,
+                    value: d.abs(),
+                }),
+            }
+        }
+    }
+
+    defects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_each_kind() {
+        let mut signal: Vec<f32> = (0..48000)
+            .map(|i| 0.5 * (2. * std::f32::consts::PI * 100. * i as f32 / 48000.).sin())
+            .collect();
+        signal[10000..10010].iter_mut().for_each(|x| *x = 1.);
+        signal[20000..21000].iter_mut().for_each(|x| *x = 0.);
+        signal[30000] += 0.5;
+        let kinds: Vec<DefectKind> = detect(&signal, 48000).iter().map(|d| d.kind).collect();
+
+        assert!(kinds.contains(&DefectKind::Clipping));
+        assert!(kinds.contains(&DefectKind::Dropout));
+        assert!(kinds.contains(&DefectKind::Click));
+        assert!(!kinds.contains(&DefectKind::DcOffset));
+    }
+
+    #[test]
+    fn dc_offset_is_the_mean_of_its_blocks() {
+        let signal: Vec<f32> = [0.02, 0.04, 0.06]
+            .iter()
+            .flat_map(|dc| vec![*dc; 100])
+            .collect();
+        let found = dc_offset(&signal, 100);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].start, found[0].end), (0, 300));
+        assert!((found[0].value - 0.04).abs() < 1e-6, "{}", found[0].value);
+    }
+}
@@ -0,0 +1,176 @@
+//! Harmonic distortion and noise of a steady sine recording.
+use std::f32::consts::PI;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::partials::interpolate_peak;
+
+/// Highest harmonic number measured.
+const MAX_HARMONIC: usize = 10;
+
+/// Longest analysis segment, taken from the middle of the recording.
+const MAX_FFT: usize = 1 << 16;
+
+/// Half width in bins of the Blackman-Harris main lobe.
+const LOBE_BINS: usize = 4;
+
+/// Quality factor of the notch removing the fundamental.
+const NOTCH_Q: f32 = 5.;
+
+/// Notch output ignored while the filter settles.
+const SETTLE_SECONDS: f32 = 0.1;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Harmonic {
+    /// 1 for the fundamental
+    pub number: usize,
+    pub hz: f32,
+    /// Level in dB relative to a full-scale sine
+    pub level: f32,
+}
+
+/// Distortion measurements, with ratios in dB relative to the fundamental.
+#[derive(Clone, Debug)]
+pub struct Distortion {
+    pub fundamental: Harmonic,
+    /// Harmonics from the second upwards, below Nyquist
+    pub harmonics: Vec<Harmonic>,
+    pub thd: f32,
+    pub thd_n: f32,
+    pub sinad: f32,
+    pub snr: f32,
+}
+
+/// Express a ratio in dB as a percentage.
+pub fn percent(db: f32) -> f32 {
+    100. * 10f32.powf(db / 20.)
+}
+
+/// Measure a recording of a sine tone.  None when it's too short.
+pub fn analyze(signal: &[f32], sample_rate: u32) -> Option<Distortion> {
+    let size = MAX_FFT.min(1 << signal.len().checked_ilog2()?);
+    if size < 1024 {
+        return None;
+    }
+
+    let start = (signal.len() - size) / 2;
+    let window = blackman_harris(size);
+    let power = power_spectrum(&signal[start..start + size], &window);
+    let bin_hz = sample_rate as f32 / size as f32;
+    let norm = 4. / (size as f32 * window.iter().map(|w| w * w).sum::<f32>());
+
+    // Amplitude squared of the component around a frequency
+    let component = |hz: f32| {
+        let bin = (hz / bin_hz).round() as usize;
+        let bins = bin.saturating_sub(LOBE_BINS)..(bin + LOBE_BINS + 1).min(power.len());
+        power[bins].iter().sum::<f32>() * norm
+    };
+    let harmonic = |number: usize, hz: f32| Harmonic {
+        number,
+        hz,
+        level: 10. * component(hz).log10(),
+    };
+
+    let peak = (2..power.len() - 1).max_by(|a, b| power[*a].total_cmp(&power[*b]))?;
+    let (offset, _) = interpolate_peak(
+        10. * power[peak - 1].log10(),
+        10. * power[peak].log10(),
+        10. * power[peak + 1].log10(),
+    );
+    let fundamental = harmonic(1, (peak as f32 + offset) * bin_hz);
+    let max_hz = sample_rate as f32 / 2. - (LOBE_BINS + 1) as f32 * bin_hz;
+    let harmonics: Vec<Harmonic> = (2..=MAX_HARMONIC)
+        .map(|number| number as f32 * fundamental.hz)
+        .take_while(|hz| *hz < max_hz)
+        .enumerate()
+        .map(|(i, hz)| harmonic(i + 2, hz))
+        .collect();
+
+    let fundamental_power = component(fundamental.hz) / 2.;
+    let harmonic_power: f32 = harmonics.iter().map(|h| component(h.hz) / 2.).sum();
+    let residual = notch(signal, fundamental.hz, sample_rate);
+    let settle = ((SETTLE_SECONDS * sample_rate as f32) as usize).min(residual.len() / 2);
+    let residual = &residual[settle..];
+    let residual_power = residual.iter().map(|x| x * x).sum::<f32>() / residual.len() as f32;
+    let noise_power = (residual_power - harmonic_power).max(f32::MIN_POSITIVE);
+    let thd_n = 10. * (residual_power / fundamental_power).log10();
+
+    Some(Distortion {
+        thd: 10. * (harmonic_power / fundamental_power).log10(),
+        thd_n,
+        sinad: -thd_n,
+        snr: 10. * (fundamental_power / noise_power).log10(),
+        fundamental,
+        harmonics,
+    })
+}
+
+/// Four-term Blackman-Harris window, with sidelobes below -92 dB.
+fn blackman_harris(size: usize) -> Vec<f32> {
+    const A: [f32; 4] = [0.35875, 0.48829, 0.14128, 0.01168];
+
+    (0..size)
+        .map(|i| {
+            let x = 2. * PI * i as f32 / size as f32;
+            A[0] - A[1] * x.cos() + A[2] * (2. * x).cos() - A[3] * (3. * x).cos()
+        })
+        .collect()
+}
+
+/// Squared magnitudes of the positive frequency bins.
+fn power_spectrum(signal: &[f32], window: &[f32]) -> Vec<f32> {
+    let mut buffer: Vec<Complex<f32>> = signal
+        .iter()
+        .zip(window)
+        .map(|(x, w)| Complex { re: x * w, im: 0. })
+        .collect();
+
+    FftPlanner::new()
+        .plan_fft_forward(buffer.len())
+        .process(&mut buffer);
+    buffer[..signal.len() / 2 + 1]
+        .iter()
+        .map(|x| x.norm_sqr())
+        .collect()
+}
+
+/// Remove a frequency with a second order notch filter.
+fn notch(signal: &[f32], hz: f32, sample_rate: u32) -> Vec<f32> {
+    let omega = 2. * PI * hz / sample_rate as f32;
+    let alpha = omega.sin() / (2. * NOTCH_Q);
+    let a0 = 1. + alpha;
+    let b = [1. / a0, -2. * omega.cos() / a0, 1. / a0];
+    let a = [-2. * omega.cos() / a0, (1. - alpha) / a0];
+    let (mut x1, mut x2, mut y1, mut y2) = (0., 0., 0., 0.);
+
+    signal
+        .iter()
+        .map(|x| {
+            let y = b[0] * x + b[1] * x1 + b[2] * x2 - a[0] * y1 - a[1] * y2;
+            (x2, x1, y2, y1) = (x1, *x, y1, y);
+            y
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_harmonic() {
+        let signal: Vec<f32> = (0..48000)
+            .map(|i| {
+                let t = i as f32 / 48000.;
+                0.5 * (2. * PI * 1000. * t).sin() + 0.005 * (2. * PI * 2000. * t).sin()
+            })
+            .collect();
+        let distortion = analyze(&signal, 48000).unwrap();
+
+        assert!((distortion.fundamental.hz - 1000.).abs() < 0.1);
+        assert!((distortion.fundamental.level + 6.02).abs() < 0.1);
+        assert!((distortion.harmonics[0].level + 46.02).abs() < 0.1);
+        assert!((distortion.thd + 40.).abs() < 0.1, "{}", distortion.thd);
+        assert!((distortion.thd_n + 40.).abs() < 0.5, "{}", distortion.thd_n);
+    }
+}
@@ -0,0 +1,258 @@
+//! Chroma features, global key and chord estimates.
+use std::fmt;
+
+use super::StftGrid;
+
+const PITCH_CLASSES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Krumhansl-Kessler probe tone ratings starting from the tonic.
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Frequency range folded into chroma.
+const MIN_HZ: f32 = 55.;
+const MAX_HZ: f32 = 5000.;
+
+/// Frames quieter than the loudest by more than this have no chord.
+const CHORD_RANGE_DB: f32 = 40.;
+
+/// Sharpness of the emission probabilities from template similarity.
+const EMISSION_SCALE: f32 = 20.;
+
+/// Template similarity assigned to the no-chord state.
+const NO_CHORD_SIMILARITY: f32 = 0.55;
+
+/// Chance of changing chord from one frame to the next.
+const CHANGE_PROBABILITY: f32 = 0.02;
+
+/// Energy in each pitch class, normalized to sum to one.
+pub type Chroma = [f32; 12];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Key {
+    /// Pitch class of the tonic, 0 for C
+    pub tonic: usize,
+    pub minor: bool,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.minor { "minor" } else { "major" };
+        write!(f, "{} {mode}", PITCH_CLASSES[self.tonic])
+    }
+}
+
+/// A major or minor triad, or no chord.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Chord(pub Option<Key>);
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(key) if key.minor => write!(f, "{}m", PITCH_CLASSES[key.tonic]),
+            Some(key) => write!(f, "{}", PITCH_CLASSES[key.tonic]),
+            None => write!(f, "N"),
+        }
+    }
+}
+
+/// Chroma of each frame, or None for frames too quiet to tell.
+pub fn chroma(frames: &[Vec<f32>], grid: &StftGrid) -> Vec<Option<Chroma>> {
+    let classes: Vec<Option<usize>> = (0..grid.window_size / 2 + 1)
+        .map(|bin| {
+            let hz = grid.bin_hz(bin as f32);
+            (MIN_HZ..MAX_HZ)
+                .contains(&hz)
+                .then(|| ((12. * (hz / 440.).log2()).round() as i32 + 69).rem_euclid(12) as usize)
+        })
+        .collect();
+    let powers: Vec<f32> = frames
+        .iter()
+        .map(|frame| frame.iter().map(|level| 10f32.powf(level / 10.)).sum())
+        .collect();
+    let loudest = powers.iter().copied().fold(0., f32::max);
+    let quietest = loudest * 10f32.powf(-CHORD_RANGE_DB / 10.);
+
+    frames
+        .iter()
+        .zip(powers)
+        .map(|(frame, power)| {
+            if power <= quietest {
+                return None;
+            }
+
+            let mut chroma = [0.; 12];
+            for (level, class) in frame.iter().zip(&classes) {
+                if let Some(class) = class {
+                    chroma[*class] += 10f32.powf(level / 20.);
+                }
+            }
+
+            let total: f32 = chroma.iter().sum();
+            (total > 0.).then(|| chroma.map(|c| c / total))
+        })
+        .collect()
+}
+
+fn correlation(a: &[f32; 12], b: &[f32; 12]) -> f32 {
+    let mean = |x: &[f32; 12]| x.iter().sum::<f32>() / 12.;
+    let (ma, mb) = (mean(a), mean(b));
+    let (mut ab, mut aa, mut bb) = (0., 0., 0.);
+
+    for (x, y) in a.iter().zip(b) {
+        ab += (x - ma) * (y - mb);
+        aa += (x - ma).powi(2);
+        bb += (y - mb).powi(2);
+    }
+
+    ab / (aa * bb).sqrt().max(f32::MIN_POSITIVE)
+}
+
+fn rotate(profile: &[f32; 12], tonic: usize) -> [f32; 12] {
+    std::array::from_fn(|class| profile[(class + 12 - tonic) % 12])
+}
+
+/// Key whose profile best correlates with the total chroma (Krumhansl-Schmuckler).
+pub fn key(chroma: &[Option<Chroma>]) -> Option<Key> {
+    let mut total = [0.; 12];
+    chroma.iter().flatten().for_each(|c| {
+        total.iter_mut().zip(c).for_each(|(t, c)| *t += c);
+    });
+    if total.iter().all(|t| *t == 0.) {
+        return None;
+    }
+
+    (0..24)
+        .map(|i| Key {
+            tonic: i % 12,
+            minor: i >= 12,
+        })
+        .max_by(|a, b| {
+            let score = |key: &Key| {
+                let profile = if key.minor {
+                    &MINOR_PROFILE
+                } else {
+                    &MAJOR_PROFILE
+                };
+                correlation(&total, &rotate(profile, key.tonic))
+            };
+            score(a).total_cmp(&score(b))
+        })
+}
+
+/// The 24 triads followed by no chord.
+fn chord_states() -> Vec<Chord> {
+    (0..24)
+        .map(|i| {
+            Chord(Some(Key {
+                tonic: i % 12,
+                minor: i >= 12,
+            }))
+        })
+        .chain([Chord(None)])
+        .collect()
+}
+
+/// Cosine similarity of chroma with a triad template.
+fn similarity(chroma: &Chroma, chord: &Chord) -> f32 {
+    let Some(key) = chord.0 else {
+        return NO_CHORD_SIMILARITY;
+    };
+    let third = if key.minor { 3 } else { 4 };
+    let tones = [key.tonic, (key.tonic + third) % 12, (key.tonic + 7) % 12];
+    let norm = chroma.iter().map(|c| c * c).sum::<f32>().sqrt() * 3f32.sqrt();
+
+    tones.iter().map(|t| chroma[*t]).sum::<f32>() / norm.max(f32::MIN_POSITIVE)
+}
+
+/// Chord of each frame by template matching, smoothed with a Viterbi pass
+/// over an HMM that favours staying on the same chord.
+pub fn chords(chroma: &[Option<Chroma>]) -> Vec<Chord> {
+    let states = chord_states();
+    let n = states.len();
+    let stay = (1. - CHANGE_PROBABILITY).ln();
+    let change = (CHANGE_PROBABILITY / (n - 1) as f32).ln();
+    let emission = |chroma: &Option<Chroma>| -> Vec<f32> {
+        let scores: Vec<f32> = states
+            .iter()
+            .map(|chord| match chroma {
+                Some(chroma) => EMISSION_SCALE * similarity(chroma, chord),
+                None if chord.0.is_none() => 0.,
+                None => -EMISSION_SCALE,
+            })
+            .collect();
+        let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let log_total = scores.iter().map(|s| (s - max).exp()).sum::<f32>().ln() + max;
+
+        scores.iter().map(|s| s - log_total).collect()
+    };
+
+    let mut score = match chroma.first() {
+        Some(first) => emission(first),
+        None => return Vec::new(),
+    };
+    let mut back: Vec<Vec<usize>> = Vec::with_capacity(chroma.len());
+
+    for frame in &chroma[1..] {
+        let best = (0..n)
+            .max_by(|a, b| score[*a].total_cmp(&score[*b]))
+            .unwrap();
+        let (pointers, next): (Vec<usize>, Vec<f32>) = emission(frame)
+            .iter()
+            .enumerate()
+            .map(|(state, e)| {
+                if score[state] + stay >= score[best] + change {
+                    (state, score[state] + stay + e)
+                } else {
+                    (best, score[best] + change + e)
+                }
+            })
+            .unzip();
+
+        back.push(pointers);
+        score = next;
+    }
+
+    let mut state = (0..n)
+        .max_by(|a, b| score[*a].total_cmp(&score[*b]))
+        .unwrap();
+    let mut path = vec![state];
+    for pointers in back.iter().rev() {
+        state = pointers[state];
+        path.push(state);
+    }
+    path.reverse();
+
+    path.iter().map(|state| states[*state]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn c_major_cadence() {
+        let triad = |tones: [usize; 3]| {
+            let mut chroma = [0.01; 12];
+            tones.iter().for_each(|t| chroma[*t] = 0.3);
+            Some(chroma)
+        };
+        let frames: Vec<Option<Chroma>> = std::iter::repeat_n(triad([0, 4, 7]), 20)
+            .chain(std::iter::repeat_n(triad([5, 9, 0]), 10))
+            .chain(std::iter::repeat_n(triad([7, 11, 2]), 10))
+            .chain(std::iter::repeat_n(triad([0, 4, 7]), 20))
+            .collect();
+        let chords: Vec<String> = chords(&frames).iter().map(|c| c.to_string()).collect();
+
+        assert_eq!(key(&frames).unwrap().to_string(), "C major");
+        assert_eq!(chords[10], "C");
+        assert_eq!(chords[25], "F");
+        assert_eq!(chords[35], "G");
+    }
+}
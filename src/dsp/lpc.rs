@@ -0,0 +1,232 @@
+//! Linear prediction envelopes and formants of STFT frames.
+//!
+//! Autocorrelations come from the power spectrum of each frame, so the
+//! models line up with the spectrogram frames without another pass over
+//! the signal.
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use super::{partials::interpolate_peak, StftGrid};
+use crate::timecode::Timecode;
+
+/// Coefficient of the pre-emphasis filter flattening the spectral tilt.
+const PRE_EMPHASIS: f64 = 0.97;
+
+/// Formants are searched below this frequency, as if resampled to twice it.
+const FORMANT_MAX_HZ: f32 = 5000.;
+
+/// Model order for formants, two poles per kHz of bandwidth plus two.
+const FORMANT_ORDER: usize = 12;
+
+/// Points the formant envelope is evaluated at.
+const FORMANT_RESOLUTION: usize = 512;
+
+/// Lowest frequency reported as a formant.
+const MIN_FORMANT_HZ: f32 = 90.;
+
+/// Frames quieter than the loudest by more than this have no formants.
+const FORMANT_RANGE_DB: f32 = 50.;
+
+/// Model order following the usual rule of one pole pair per kHz.
+pub fn auto_order(sample_rate: u32) -> usize {
+    2 + sample_rate as usize / 1000
+}
+
+/// Prediction coefficients, starting with 1, and the prediction error power
+/// from an autocorrelation sequence.
+pub fn levinson(r: &[f64]) -> (Vec<f64>, f64) {
+    let order = r.len() - 1;
+    let mut a = vec![0.; order + 1];
+    let mut error = r[0];
+    a[0] = 1.;
+
+    for i in 1..=order {
+        if error <= 0. {
+            break;
+        }
+
+        let acc: f64 = (0..i).map(|j| a[j] * r[i - j]).sum();
+        let k = -acc / error;
+        let previous = a.clone();
+
+        for j in 1..i {
+            a[j] = previous[j] + k * previous[i - j];
+        }
+        a[i] = k;
+        error *= 1. - k * k;
+    }
+
+    (a, error)
+}
+
+/// Gain of the pre-emphasis filter at an angular frequency.
+fn emphasis(omega: f64) -> f64 {
+    1. + PRE_EMPHASIS * PRE_EMPHASIS - 2. * PRE_EMPHASIS * omega.cos()
+}
+
+/// Power of the all-pole model at an angular frequency.
+fn model_power(a: &[f64], error: f64, omega: f64) -> f64 {
+    let (re, im) = a.iter().enumerate().fold((0., 0.), |(re, im), (k, a)| {
+        (
+            re + a * (k as f64 * omega).cos(),
+            im - a * (k as f64 * omega).sin(),
+        )
+    });
+
+    error / (re * re + im * im)
+}
+
+/// Model of a pre-emphasized half spectrum of levels in dB, treating its
+/// last bin as Nyquist.
+fn model(levels: &[f32], order: usize) -> (Vec<f64>, f64) {
+    let half = levels.len().saturating_sub(1).max(1);
+    let power: Vec<f64> = levels
+        .iter()
+        .enumerate()
+        .map(|(bin, level)| {
+            10f64.powf(*level as f64 / 10.) * emphasis(PI * bin as f64 / half as f64)
+        })
+        .collect();
+    let r: Vec<f64> = (0..=order.min(2 * half - 1))
+        .map(|lag| {
+            power
+                .iter()
+                .enumerate()
+                .map(|(bin, p)| {
+                    let weight = if bin == 0 || bin == half { 1. } else { 2. };
+                    weight * p * (PI * (bin * lag) as f64 / half as f64).cos()
+                })
+                .sum::<f64>()
+                / (2 * half) as f64
+        })
+        .collect();
+
+    levinson(&r)
+}
+
+/// LPC envelope in dB of a frame of levels, on the same scale and bins.
+pub fn envelope(levels: &[f32], order: usize) -> Vec<f32> {
+    let half = levels.len().saturating_sub(1).max(1);
+    let (a, error) = model(levels, order);
+
+    (0..levels.len())
+        .map(|bin| {
+            let omega = PI * bin as f64 / half as f64;
+            (10. * (model_power(&a, error, omega) / emphasis(omega)).log10()) as f32
+        })
+        .collect()
+}
+
+/// Frequencies of the first four formants of each frame.
+pub fn formants(frames: &[Vec<f32>], grid: &StftGrid) -> Vec<[Option<f32>; 4]> {
+    let bins = ((FORMANT_MAX_HZ / grid.bin_hz(1.)) as usize).min(grid.window_size / 2);
+    let max_hz = grid.bin_hz(bins as f32);
+    let loudest = frames
+        .iter()
+        .map(|frame| total_db(frame))
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    frames
+        .iter()
+        .map(|frame| {
+            let mut result = [None; 4];
+
+            if frame.len() <= bins || total_db(frame) < loudest - FORMANT_RANGE_DB {
+                return result;
+            }
+
+            let (a, error) = model(&frame[..=bins], FORMANT_ORDER);
+            let response: Vec<f32> = (0..=FORMANT_RESOLUTION)
+                .map(|i| {
+                    let omega = PI * i as f64 / FORMANT_RESOLUTION as f64;
+                    (10. * model_power(&a, error, omega).log10()) as f32
+                })
+                .collect();
+            let peaks = response
+                .windows(3)
+                .enumerate()
+                .filter(|(_, w)| w[1] > w[0] && w[1] >= w[2])
+                .map(|(i, w)| {
+                    let (offset, _) = interpolate_peak(w[0], w[1], w[2]);
+                    (i as f32 + 1. + offset) / FORMANT_RESOLUTION as f32 * max_hz
+                });
+
+            peaks
+                .filter(|hz| *hz >= MIN_FORMANT_HZ)
+                .take(4)
+                .enumerate()
+                .for_each(|(i, hz)| result[i] = Some(hz));
+            result
+        })
+        .collect()
+}
+
+fn total_db(frame: &[f32]) -> f32 {
+    10. * frame
+        .iter()
+        .map(|level| 10f32.powf(level / 10.))
+        .sum::<f32>()
+        .log10()
+}
+
+/// Write formant tracks with one row per frame, leaving missing formants
+/// empty.
+pub fn write_csv(
+    formants: &[[Option<f32>; 4]],
+    grid: &StftGrid,
+    timecode: &Timecode,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(writer, "{},f1_hz,f2_hz,f3_hz,f4_hz", timecode.header())?;
+    for (frame, formants) in formants.iter().enumerate() {
+        let time = timecode.format_export(grid.frame_seconds(frame as f32));
+        write!(writer, "{time}")?;
+        for hz in formants {
+            match hz {
+                Some(hz) => write!(writer, ",{hz:.1}")?,
+                None => write!(writer, ",")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_order_process() {
+        let r: Vec<f64> = (0..4).map(|k| 0.9f64.powi(k)).collect();
+        let (a, error) = levinson(&r);
+
+        assert!((a[1] + 0.9).abs() < 1e-9);
+        assert!(a[2].abs() < 1e-9 && a[3].abs() < 1e-9);
+        assert!((error - 0.19).abs() < 1e-9);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 1024,
+            hop_size: 480,
+            offset: 0.,
+        };
+        let mut csv = Vec::new();
+        write_csv(
+            &[[Some(700.), Some(1200.), None, None]],
+            &grid,
+            &Timecode::default(),
+            &mut csv,
+        )
+        .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("time_s,f1_hz,f2_hz,f3_hz,f4_hz"));
+        assert_eq!(lines.next(), Some("0.010667,700.0,1200.0,,"));
+    }
+}
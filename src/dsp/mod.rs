@@ -0,0 +1,89 @@
+pub mod activity;
+pub mod bands;
+pub mod decay;
+pub mod defects;
+pub mod distortion;
+pub mod harmony;
+pub mod lpc;
+pub mod modulation;
+pub mod noise;
+pub mod normalize;
+pub mod partials;
+pub mod resample;
+pub mod segments;
+pub mod spectrum;
+pub mod sweep;
+pub mod weighting;
+
+/// Reproducible white noise between -1 and 1 for tests.
+#[cfg(test)]
+pub(crate) fn white_noise(seed: u32) -> impl FnMut() -> f32 {
+    let mut state = seed;
+
+    move || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        state as f32 / u32::MAX as f32 * 2. - 1.
+    }
+}
+
+/// STFT levels in decibels, one vector of bins per frame.
+#[derive(Clone, Debug)]
+pub struct Spectrogram {
+    pub frames: Vec<Vec<f32>>,
+    pub grid: StftGrid,
+}
+
+impl Spectrogram {
+    /// Horizontal position of a time in normalized analysis coordinates.
+    pub fn seconds_x(&self, seconds: f64) -> f32 {
+        let frame = ((seconds - self.grid.offset) * self.grid.sample_rate as f64
+            - self.grid.window_size as f64 / 2.)
+            / self.grid.hop_size as f64;
+
+        (frame / (self.frames.len() as f64 - 1.).max(1.)) as f32
+    }
+
+    /// Time at a horizontal position in normalized analysis coordinates.
+    pub fn x_seconds(&self, x: f32) -> f64 {
+        self.grid
+            .frame_seconds(x * (self.frames.len() as f32 - 1.).max(1.))
+    }
+}
+
+/// Layout of the STFT frames in time and frequency.
+#[derive(Copy, Clone, Debug)]
+pub struct StftGrid {
+    pub sample_rate: u32,
+    pub window_size: usize,
+    pub hop_size: usize,
+    /// Time in seconds of the first analysed sample within the file
+    pub offset: f64,
+}
+
+impl StftGrid {
+    /// Frequency in Hz of a possibly fractional bin.
+    pub fn bin_hz(&self, bin: f32) -> f32 {
+        bin * self.sample_rate as f32 / self.window_size as f32
+    }
+
+    /// Time in seconds at the center of a frame.
+    pub fn frame_seconds(&self, frame: f32) -> f64 {
+        self.offset
+            + (frame as f64 * self.hop_size as f64 + self.window_size as f64 / 2.)
+                / self.sample_rate as f64
+    }
+
+    /// Time in seconds of a sample of the analysed signal.
+    pub fn sample_seconds(&self, sample: usize) -> f64 {
+        self.offset + sample as f64 / self.sample_rate as f64
+    }
+
+    /// Nearest frame centered on a time in seconds.
+    pub fn seconds_frame(&self, seconds: f64) -> usize {
+        let frame = ((seconds - self.offset) * self.sample_rate as f64
+            - self.window_size as f64 / 2.)
+            / self.hop_size as f64;
+
+        frame.round().max(0.) as usize
+    }
+}
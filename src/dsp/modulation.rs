@@ -0,0 +1,132 @@
+//! Modulation spectra of band envelopes.
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::{
+    bands::{band_levels, Band},
+    weighting::Weighting,
+    StftGrid,
+};
+
+/// Highest modulation frequency kept.
+const MAX_MODULATION_HZ: f32 = 64.;
+
+/// Depth given to silent bands and to modulation frequencies absent from
+/// the envelope.
+const MIN_DEPTH_DB: f32 = -120.;
+
+/// Acoustic frequency against modulation frequency.
+#[derive(Clone, Debug)]
+pub struct ModulationSpectrum {
+    /// Modulation depth in dB, one vector of bands per modulation frequency
+    /// starting from the lowest above zero.  0 dB is full modulation.
+    pub columns: Vec<Vec<f32>>,
+    /// Spacing of the modulation frequencies in Hz
+    pub resolution: f32,
+}
+
+/// Spectrum of each band's amplitude envelope over the STFT frames.
+pub fn modulation_spectrum(
+    frames: &[Vec<f32>],
+    bands: &[Band],
+    grid: &StftGrid,
+) -> ModulationSpectrum {
+    let levels = band_levels(frames, bands, grid, Weighting::Z);
+    let size = frames.len().next_power_of_two().max(2);
+    let frame_rate = grid.sample_rate as f32 / grid.hop_size as f32;
+    let resolution = frame_rate / size as f32;
+    let count = ((MAX_MODULATION_HZ.min(frame_rate / 2.) / resolution) as usize)
+        .min(size / 2)
+        .max(1);
+    let window: Vec<f32> = (0..frames.len())
+        .map(|i| 0.5 - 0.5 * (2. * std::f32::consts::PI * i as f32 / frames.len() as f32).cos())
+        .collect();
+    let window_sum: f32 = window.iter().sum();
+    let fft = FftPlanner::new().plan_fft_forward(size);
+
+    let rows: Vec<Vec<f32>> = (0..bands.len())
+        .map(|band| {
+            let envelope: Vec<f32> = levels
+                .iter()
+                .map(|frame| 10f32.powf(frame[band] / 20.))
+                .collect();
+            let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+
+            if mean <= f32::EPSILON {
+                return vec![MIN_DEPTH_DB; count];
+            }
+
+            let mut buffer: Vec<Complex<f32>> = envelope
+                .iter()
+                .zip(&window)
+                .map(|(x, w)| Complex {
+                    re: (x - mean) * w,
+                    im: 0.,
+                })
+                .collect();
+            buffer.resize(size, Complex { re: 0., im: 0. });
+            fft.process(&mut buffer);
+
+            buffer[1..=count]
+                .iter()
+                .map(|x| (20. * (2. * x.norm() / (window_sum * mean)).log10()).max(MIN_DEPTH_DB))
+                .collect()
+        })
+        .collect();
+
+    ModulationSpectrum {
+        columns: (0..count)
+            .map(|column| rows.iter().map(|row| row[column]).collect())
+            .collect(),
+        resolution,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::bands::{bands, Fraction};
+
+    #[test]
+    fn tremolo_rate() {
+        let grid = StftGrid {
+            sample_rate: 16000,
+            window_size: 512,
+            hop_size: 128,
+            offset: 0.,
+        };
+        let signal: Vec<f32> = (0..16000 * 4)
+            .map(|i| {
+                let t = i as f32 / 16000.;
+                let tremolo = 1. + 0.5 * (2. * std::f32::consts::PI * 4. * t).sin();
+                0.5 * tremolo * (2. * std::f32::consts::PI * 1000. * t).sin()
+            })
+            .collect();
+        let (frames, _) = crate::fft::stft(&signal, "hamming", 512, 128);
+        let octaves = bands(Fraction::Octave, 16000);
+        let khz = octaves.iter().position(|b| b.nominal() == 1000.).unwrap();
+        let spectrum = modulation_spectrum(&frames, &octaves, &grid);
+        let peak = (0..spectrum.columns.len())
+            .max_by(|a, b| spectrum.columns[*a][khz].total_cmp(&spectrum.columns[*b][khz]))
+            .unwrap();
+
+        assert!(((peak + 1) as f32 * spectrum.resolution - 4.).abs() <= spectrum.resolution);
+    }
+
+    #[test]
+    fn silence_stays_finite() {
+        let grid = StftGrid {
+            sample_rate: 16000,
+            window_size: 512,
+            hop_size: 128,
+            offset: 0.,
+        };
+        let (frames, _) = crate::fft::stft(&vec![0.; 16000], "hamming", 512, 128);
+        let spectrum = modulation_spectrum(&frames, &bands(Fraction::Third, 16000), &grid);
+
+        assert!(spectrum
+            .columns
+            .iter()
+            .flatten()
+            .all(|depth| *depth == MIN_DEPTH_DB));
+    }
+}
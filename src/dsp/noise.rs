@@ -0,0 +1,250 @@
+//! Noise floor estimation and signal-to-noise ratios.
+use std::io::{self, Write};
+
+use strum_macros::{Display, EnumIter};
+
+use super::{
+    bands::{Band, BandFilter},
+    spectrum::long_term_average,
+    StftGrid,
+};
+
+/// Fraction of frames below the noise floor in percentile estimation.
+const PERCENTILE: f32 = 0.1;
+
+/// Compensates the percentile lying below the mean power of noise.  The
+/// power of a bin of noise is exponentially distributed, so its 10th
+/// percentile is -ln 0.9 times the mean.
+const PERCENTILE_BIAS_DB: f32 = 9.78;
+
+/// Smoothing factor of the power tracked by minimum statistics.
+const SMOOTHING: f32 = 0.85;
+
+/// Length in seconds of the minimum search window.
+const SEARCH_SECONDS: f64 = 1.5;
+
+/// Compensates the minimum of smoothed power lying below its mean.
+const MINIMUM_BIAS_DB: f32 = 3.;
+
+/// Smoothed power this far above the noise is taken for a loud passage and
+/// let go of as soon as the power falls.
+const RELEASE_DB: f32 = 10.;
+
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq, clap::ValueEnum)]
+pub enum NoiseEstimator {
+    /// Low percentile of each bin's level across frames.  Reads high in
+    /// bins where the signal fills most of the frames.
+    #[default]
+    Percentile,
+    /// Sliding minimum of smoothed power, averaged over time
+    #[strum(serialize = "Minimum statistics")]
+    MinimumStatistics,
+}
+
+impl NoiseEstimator {
+    /// Noise floor of each bin in dB.
+    pub fn floor(&self, frames: &[Vec<f32>], grid: &StftGrid) -> Vec<f32> {
+        let bins = frames.first().map(|frame| frame.len()).unwrap_or(0);
+
+        (0..bins)
+            .map(|bin| {
+                let row: Vec<f32> = frames.iter().map(|frame| frame[bin]).collect();
+
+                match self {
+                    Self::Percentile => percentile(row, PERCENTILE) + PERCENTILE_BIAS_DB,
+                    Self::MinimumStatistics => minimum_statistics(&row, grid),
+                }
+            })
+            .collect()
+    }
+}
+
+fn percentile(mut row: Vec<f32>, fraction: f32) -> f32 {
+    if row.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+
+    let index = ((row.len() - 1) as f32 * fraction).round() as usize;
+    *row.select_nth_unstable_by(index, |a, b| a.total_cmp(b)).1
+}
+
+fn minimum_statistics(row: &[f32], grid: &StftGrid) -> f32 {
+    let window =
+        (SEARCH_SECONDS * grid.sample_rate as f64 / grid.hop_size as f64).round() as usize + 1;
+    let bias = 10f32.powf(MINIMUM_BIAS_DB / 10.);
+    let release = 10f32.powf(RELEASE_DB / 10.);
+    let mut smoothed: Vec<f32> = Vec::with_capacity(row.len());
+    let mut minima: Vec<f32> = Vec::with_capacity(row.len());
+
+    for (frame, level) in row.iter().enumerate() {
+        let power = 10f32.powf(level / 10.);
+        let previous = smoothed.last().copied().unwrap_or(power);
+        let noise = minima.last().map_or(power, |min| min * bias);
+        // Without letting go, the power takes seconds to fall back from a
+        // loud passage and the minimum misses the noise in between.
+        let smoothing = if previous > noise * release && power < previous {
+            0.
+        } else {
+            SMOOTHING
+        };
+
+        smoothed.push(smoothing * previous + (1. - smoothing) * power);
+        minima.push(
+            smoothed[(frame + 1).saturating_sub(window)..]
+                .iter()
+                .fold(f32::INFINITY, |min, power| min.min(*power)),
+        );
+    }
+    let mean = minima.iter().sum::<f32>() / minima.len().max(1) as f32;
+
+    10. * mean.log10() + MINIMUM_BIAS_DB
+}
+
+/// Signal and noise levels of one band, or of the whole spectrum.
+#[derive(Clone, Debug)]
+pub struct NoiseReport {
+    /// None for broadband
+    pub band: Option<Band>,
+    /// Average level including noise
+    pub signal: f32,
+    pub noise: f32,
+    /// Ratio of the signal, with the noise removed, to the noise
+    pub snr: f32,
+}
+
+impl NoiseReport {
+    fn new(band: Option<Band>, signal: f32, noise: f32) -> Self {
+        let excess = 10f32.powf(signal / 10.) - 10f32.powf(noise / 10.);
+
+        NoiseReport {
+            band,
+            signal,
+            noise,
+            snr: 10. * excess.max(f32::MIN_POSITIVE).log10() - noise,
+        }
+    }
+}
+
+/// Broadband report followed by one report per band, with per-bin weighting
+/// gains in dB.
+pub fn report(
+    frames: &[Vec<f32>],
+    floor: &[f32],
+    bands: &[Band],
+    grid: &StftGrid,
+    gains: &[f32],
+) -> Vec<NoiseReport> {
+    let nyquist = grid.sample_rate as f32 / 2.;
+    let broadband = Band {
+        center: nyquist / 2.,
+        low: 0.,
+        high: nyquist,
+    };
+    let all: Vec<Band> = std::iter::once(broadband)
+        .chain(bands.iter().copied())
+        .collect();
+    let filter = BandFilter::new(&all, grid);
+    let average = long_term_average(frames);
+
+    all.iter()
+        .enumerate()
+        .zip(filter.levels(&average, gains))
+        .zip(filter.levels(floor, gains))
+        .map(|(((i, band), signal), noise)| {
+            NoiseReport::new((i > 0).then_some(*band), signal, noise)
+        })
+        .collect()
+}
+
+/// Write the reports as CSV, labelling the broadband row `all`.
+pub fn write_csv(reports: &[NoiseReport], mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "band_hz,signal_db,noise_db,snr_db")?;
+    for report in reports {
+        let band = report
+            .band
+            .map(|band| band.label())
+            .unwrap_or_else(|| "all".to_string());
+
+        writeln!(
+            writer,
+            "{band},{:.2},{:.2},{:.2}",
+            report.signal, report.noise, report.snr
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::{
+        bands::{bands, Fraction},
+        white_noise,
+    };
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn tone_over_known_noise() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 2048,
+            hop_size: 1024,
+            offset: 0.,
+        };
+        let mut noise = white_noise(1);
+        let noise_only: Vec<f32> = (0..192000).map(|_| 0.01 * noise()).collect();
+        // A quarter second of tone every two seconds, with the noise alone
+        // in between.
+        let signal: Vec<f32> = noise_only
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let tone = (2. * std::f32::consts::PI * 1000. * i as f32 / 48000.).sin();
+                n + if i / 12000 % 8 == 3 { 0.5 * tone } else { 0. }
+            })
+            .collect();
+        let octaves = bands(Fraction::Octave, 48000);
+        // Reports start with the broadband one.
+        let khz = 1 + octaves.iter().position(|b| b.nominal() == 1000.).unwrap();
+        let (frames, _) = crate::fft::stft(&noise_only, "hamming", 2048, 1024);
+        let gains = vec![0.; frames[0].len()];
+        let floor = vec![f32::NEG_INFINITY; frames[0].len()];
+        let known = report(&frames, &floor, &octaves, &grid, &gains);
+        let (frames, _) = crate::fft::stft(&signal, "hamming", 2048, 1024);
+
+        for estimator in NoiseEstimator::iter() {
+            let floor = estimator.floor(&frames, &grid);
+            let reports = report(&frames, &floor, &octaves, &grid, &gains);
+            for band in [khz, khz + 3] {
+                let error = reports[band].noise - known[band].signal;
+                assert!(error.abs() < 1.5, "{estimator} {band}: {error}");
+            }
+            assert!(reports[khz].snr > 40., "{estimator}: {}", reports[khz].snr);
+        }
+    }
+
+    #[test]
+    fn minimum_statistics_ignores_the_offset() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 2048,
+            hop_size: 1024,
+            offset: 0.,
+        };
+        // Quiet, then loud for about a second: a search window collapsed to
+        // a couple of frames would follow the loud part.
+        let row: Vec<f32> = (0..200).map(|i| if i < 150 { -60. } else { 0. }).collect();
+        let start = minimum_statistics(&row, &grid);
+        let later = minimum_statistics(
+            &row,
+            &StftGrid {
+                offset: 30.,
+                ..grid
+            },
+        );
+
+        assert_eq!(start, later);
+        assert!((later + 57.).abs() < 0.1, "{later}");
+    }
+}
@@ -0,0 +1,127 @@
+//! Display modes that flatten the spectrogram before colour mapping.
+use strum_macros::{Display, EnumIter};
+
+/// Level that relative modes place their reference at, so that both quieter
+/// and louder detail stays inside the -150..0 dB colour range.
+const REFERENCE_DB: f32 = -90.;
+
+/// Half width in bins of the envelope removed by whitening.
+const WHITENING_RADIUS: usize = 8;
+
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq, clap::ValueEnum)]
+pub enum DisplayMode {
+    /// Levels as analysed
+    #[default]
+    Absolute,
+    /// Each frequency row relative to its long-term median
+    #[strum(serialize = "Row median")]
+    RowMedian,
+    /// Each frame relative to its smoothed spectral envelope
+    Whitened,
+}
+
+impl DisplayMode {
+    /// Add per-bin gains in dB to every frame, then normalize for display.
+    pub fn apply(&self, frames: &[Vec<f32>], gains: &[f32]) -> Vec<Vec<f32>> {
+        let mut frames: Vec<Vec<f32>> = frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .iter()
+                    .enumerate()
+                    .map(|(bin, level)| level + gains.get(bin).copied().unwrap_or(0.))
+                    .collect()
+            })
+            .collect();
+
+        match self {
+            Self::Absolute => {}
+            Self::RowMedian => {
+                let medians = row_medians(&frames);
+
+                frames.iter_mut().for_each(|frame| {
+                    frame
+                        .iter_mut()
+                        .zip(&medians)
+                        .for_each(|(level, median)| *level += REFERENCE_DB - median);
+                });
+            }
+            Self::Whitened => {
+                frames.iter_mut().for_each(|frame| {
+                    let envelope = envelope(frame, WHITENING_RADIUS);
+
+                    frame
+                        .iter_mut()
+                        .zip(envelope)
+                        .for_each(|(level, envelope)| *level += REFERENCE_DB - envelope);
+                });
+            }
+        }
+
+        frames
+    }
+}
+
+/// Median level of each bin across frames, ignoring silent bins.
+pub fn row_medians(frames: &[Vec<f32>]) -> Vec<f32> {
+    let bins = frames.first().map(|frame| frame.len()).unwrap_or(0);
+
+    (0..bins)
+        .map(|bin| {
+            let mut row: Vec<f32> = frames
+                .iter()
+                .map(|frame| frame[bin])
+                .filter(|level| level.is_finite())
+                .collect();
+
+            if row.is_empty() {
+                return 0.;
+            }
+
+            let middle = row.len() / 2;
+            *row.select_nth_unstable_by(middle, |a, b| a.total_cmp(b)).1
+        })
+        .collect()
+}
+
+/// Moving average of finite levels over `radius` bins on either side.
+fn envelope(frame: &[f32], radius: usize) -> Vec<f32> {
+    (0..frame.len())
+        .map(|bin| {
+            let window = &frame[bin.saturating_sub(radius)..(bin + radius + 1).min(frame.len())];
+            let (sum, count) = window
+                .iter()
+                .filter(|level| level.is_finite())
+                .fold((0., 0), |(sum, count), level| (sum + level, count + 1));
+
+            if count == 0 {
+                0.
+            } else {
+                sum / count as f32
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes() {
+        let frames = vec![vec![-30., -60.], vec![-50., -60.], vec![-40., -60.]];
+        let gains = [6., 0.];
+
+        let absolute = DisplayMode::Absolute.apply(&frames, &gains);
+        assert_eq!(absolute[0], vec![-24., -60.]);
+
+        // Every row lands its median on the reference level.
+        let median = DisplayMode::RowMedian.apply(&frames, &gains);
+        assert_eq!(median[2], vec![REFERENCE_DB, REFERENCE_DB]);
+        assert_eq!(median[0][0], REFERENCE_DB + 10.);
+
+        // A flat frame is flat after whitening, whatever its level.
+        let whitened = DisplayMode::Whitened.apply(&[vec![-70.; 32]], &[]);
+        assert!(whitened[0].iter().all(|level| *level == REFERENCE_DB));
+    }
+}
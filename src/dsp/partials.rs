@@ -0,0 +1,238 @@
+//! Sinusoidal partial tracking in the style of McAulay and Quatieri.
+use std::io::{self, Write};
+
+use super::StftGrid;
+use crate::timecode::Timecode;
+
+/// Spectral peak with parabolic interpolation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Peak {
+    /// Fractional bin of the interpolated maximum
+    pub bin: f32,
+    /// Interpolated level in decibels
+    pub level: f32,
+}
+
+/// Point of a partial track.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrackPoint {
+    pub frame: usize,
+    pub bin: f32,
+    pub level: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Track {
+    pub points: Vec<TrackPoint>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PartialParams {
+    /// Ignore peaks below this level in decibels
+    pub threshold_db: f32,
+    /// Keep only the loudest peaks in each frame
+    pub max_peaks: usize,
+    /// Largest jump in bins between consecutive frames of a track
+    pub max_deviation: f32,
+    /// Drop tracks shorter than this many frames
+    pub min_length: usize,
+}
+
+impl Default for PartialParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: -80.,
+            max_peaks: 40,
+            max_deviation: 3.,
+            min_length: 4,
+        }
+    }
+}
+
+/// Fit a parabola through three levels around a maximum, returning the
+/// offset of the vertex from the middle bin and its level.
+pub fn interpolate_peak(left: f32, center: f32, right: f32) -> (f32, f32) {
+    let denominator = left - 2. * center + right;
+
+    if denominator == 0. {
+        return (0., center);
+    }
+
+    let offset = 0.5 * (left - right) / denominator;
+
+    (offset, center - 0.25 * (left - right) * offset)
+}
+
+/// Find the loudest local maxima of a frame of decibel levels.
+pub fn find_peaks(frame: &[f32], threshold_db: f32, max_peaks: usize) -> Vec<Peak> {
+    let mut peaks: Vec<Peak> = frame
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| w[1] > threshold_db && w[1] > w[0] && w[1] >= w[2])
+        .map(|(i, w)| {
+            let (offset, level) = interpolate_peak(w[0], w[1], w[2]);
+
+            Peak {
+                bin: (i + 1) as f32 + offset,
+                level,
+            }
+        })
+        .collect();
+
+    peaks.sort_by(|a, b| b.level.total_cmp(&a.level));
+    peaks.truncate(max_peaks);
+    peaks
+}
+
+/// Link the peaks of consecutive frames into partial tracks.
+///
+/// Each live track continues with the closest unclaimed peak within
+/// `max_deviation` bins, closest pairs first.  Tracks without a match end,
+/// and peaks without a track start new ones.
+pub fn track_partials(frames: &[Vec<f32>], params: &PartialParams) -> Vec<Track> {
+    let mut finished = Vec::new();
+    let mut active: Vec<Track> = Vec::new();
+
+    for (index, frame) in frames.iter().enumerate() {
+        let peaks = find_peaks(frame, params.threshold_db, params.max_peaks);
+        let mut candidates: Vec<(f32, usize, usize)> = active
+            .iter()
+            .enumerate()
+            .flat_map(|(t, track)| {
+                let last = track.points[track.points.len() - 1];
+
+                peaks.iter().enumerate().filter_map(move |(p, peak)| {
+                    let distance = (peak.bin - last.bin).abs();
+                    (distance <= params.max_deviation).then_some((distance, t, p))
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut continuations = vec![None; active.len()];
+        let mut claimed = vec![false; peaks.len()];
+
+        for (_, t, p) in candidates {
+            if continuations[t].is_none() && !claimed[p] {
+                continuations[t] = Some(p);
+                claimed[p] = true;
+            }
+        }
+
+        let mut next_active = Vec::new();
+
+        for (mut track, continuation) in active.drain(..).zip(continuations) {
+            if let Some(p) = continuation {
+                track.points.push(TrackPoint {
+                    frame: index,
+                    bin: peaks[p].bin,
+                    level: peaks[p].level,
+                });
+                next_active.push(track);
+            } else {
+                finished.push(track);
+            }
+        }
+
+        for (peak, _) in peaks.iter().zip(claimed).filter(|(_, c)| !c) {
+            next_active.push(Track {
+                points: vec![TrackPoint {
+                    frame: index,
+                    bin: peak.bin,
+                    level: peak.level,
+                }],
+            });
+        }
+
+        active = next_active;
+    }
+
+    finished.extend(active);
+    finished.retain(|track| track.points.len() >= params.min_length);
+    finished.sort_by_key(|track| track.points[0].frame);
+    finished
+}
+
+/// Write tracks as time, frequency and amplitude rows.
+pub fn write_csv(
+    tracks: &[Track],
+    grid: &StftGrid,
+    timecode: &Timecode,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "track,{},frequency_hz,amplitude_db",
+        timecode.header()
+    )?;
+
+    for (id, track) in tracks.iter().enumerate() {
+        for point in &track.points {
+            writeln!(
+                writer,
+                "{id},{},{:.3},{:.2}",
+                timecode.format_export(grid.frame_seconds(point.frame as f32)),
+                grid.bin_hz(point.bin),
+                point.level
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn parabola_vertex() {
+        // Samples of y = 10 - (x - 0.25)^2 at x = -1, 0, 1
+        let (offset, level) = interpolate_peak(10. - 1.5625, 10. - 0.0625, 10. - 0.5625);
+        assert_float_eq!(offset, 0.25, abs <= 1e-6);
+        assert_float_eq!(level, 10., abs <= 1e-6);
+    }
+
+    #[test]
+    fn gliding_partial() {
+        let frames: Vec<Vec<f32>> = (0..8)
+            .map(|i| {
+                let mut frame = vec![-120.; 64];
+                frame[10 + i] = -10.;
+                frame[40] = -20.;
+                frame
+            })
+            .collect();
+        let tracks = track_partials(&frames, &PartialParams::default());
+
+        assert_eq!(tracks.len(), 2);
+        assert!(tracks.iter().all(|t| t.points.len() == 8));
+        assert!(tracks.iter().any(|t| t.points[7].bin == 17.));
+    }
+
+    #[test]
+    fn csv_rows() {
+        let grid = StftGrid {
+            sample_rate: 48000,
+            window_size: 1024,
+            hop_size: 480,
+            offset: 0.,
+        };
+        let tracks = [Track {
+            points: vec![TrackPoint {
+                frame: 0,
+                bin: 10.,
+                level: -12.,
+            }],
+        }];
+        let mut csv = Vec::new();
+        write_csv(&tracks, &grid, &Timecode::default(), &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("track,time_s,frequency_hz,amplitude_db"));
+        assert_eq!(lines.next(), Some("0,0.010667,468.750,-12.00"));
+    }
+}
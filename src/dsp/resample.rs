@@ -0,0 +1,212 @@
+//! Sample-rate conversion with a polyphase windowed-sinc filter.
+use std::f64::consts::PI;
+
+/// Zero crossings of the sinc on each side of the centre, more when
+/// downsampling lowers the cutoff.
+const ZERO_CROSSINGS: usize = 16;
+/// Filter phases tabulated between two input samples.  Positions between
+/// phases interpolate the coefficients linearly.
+const PHASES: usize = 256;
+/// Cutoff as a fraction of the lower Nyquist frequency, leaving room for the
+/// transition band.
+const ROLLOFF: f64 = 0.95;
+/// Taps on each side of an output at most.  Steeper downsampling gets a
+/// shorter filter, with a wider transition band, rather than a table of
+/// gigabytes.
+const MAX_HALF: usize = 64 * ZERO_CROSSINGS;
+
+/// Streaming resampler for interleaved channels.  Converting a rate to
+/// itself passes the samples through.
+#[derive(Clone, Debug)]
+pub struct Resampler {
+    /// Input frames per output frame
+    step: f64,
+    channels: usize,
+    /// Taps on each side of an output
+    half: usize,
+    /// `PHASES + 1` rows of `2 * half` coefficients
+    table: Vec<f32>,
+    /// Input frames still needed, interleaved
+    buffer: Vec<f32>,
+    /// Input frame of the next output, counted from the start of `buffer`
+    position: f64,
+    /// Input frames received so far
+    received: u64,
+    /// Output frames produced so far
+    produced: u64,
+}
+
+impl Resampler {
+    pub fn new(from: u32, to: u32, channels: usize) -> Self {
+        assert!(to > 0, "cannot resample to 0 Hz");
+
+        let step = from as f64 / to as f64;
+        let cutoff = ROLLOFF * (1. / step).min(1.);
+        let half = match from == to {
+            true => 0,
+            false => ((ZERO_CROSSINGS as f64 / cutoff).ceil() as usize).min(MAX_HALF),
+        };
+        let table = (0..=PHASES)
+            .flat_map(|phase| {
+                let fraction = phase as f64 / PHASES as f64;
+
+                (0..2 * half).map(move |k| {
+                    let t = k as f64 + 1. - half as f64 - fraction;
+                    kernel(t, cutoff, half as f64) as f32
+                })
+            })
+            .collect();
+
+        Resampler {
+            step,
+            channels: channels.max(1),
+            half,
+            table,
+            buffer: vec![0.; half * channels.max(1)],
+            position: half as f64,
+            received: 0,
+            produced: 0,
+        }
+    }
+
+    /// Resample the next interleaved frames.  Outputs lag the input by the
+    /// length of the filter until `flush`.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.half == 0 {
+            return input.to_vec();
+        }
+
+        self.received += (input.len() / self.channels) as u64;
+        self.buffer.extend_from_slice(input);
+        self.run()
+    }
+
+    /// The outputs still held back at the end of the input.
+    pub fn flush(&mut self) -> Vec<f32> {
+        if self.half == 0 {
+            return vec![];
+        }
+
+        self.buffer
+            .resize(self.buffer.len() + self.half * self.channels, 0.);
+        self.run()
+    }
+
+    /// Resample chunks of samples as they arrive, flushing after the last.
+    pub fn stream<I: Iterator<Item = Vec<f32>>>(self, chunks: I) -> Resampled<I> {
+        Resampled {
+            chunks,
+            resampler: self,
+            flushed: false,
+        }
+    }
+
+    fn run(&mut self) -> Vec<f32> {
+        let frames = self.buffer.len() / self.channels;
+        let taps = 2 * self.half;
+        let mut output = Vec::new();
+
+        while self.position as usize + self.half < frames
+            && (self.produced as f64 * self.step) < self.received as f64
+        {
+            let n = self.position as usize;
+            let phase = (self.position - n as f64) * PHASES as f64;
+            let mix = phase.fract() as f32;
+            let phase = phase as usize;
+            let before = &self.table[phase * taps..][..taps];
+            let after = &self.table[(phase + 1) * taps..][..taps];
+            let first = n + 1 - self.half;
+
+            for channel in 0..self.channels {
+                output.push(
+                    before
+                        .iter()
+                        .zip(after)
+                        .enumerate()
+                        .map(|(k, (a, b))| {
+                            self.buffer[(first + k) * self.channels + channel] * (a + (b - a) * mix)
+                        })
+                        .sum(),
+                );
+            }
+            self.position += self.step;
+            self.produced += 1;
+        }
+
+        let used = (self.position as usize + 1)
+            .saturating_sub(self.half)
+            .min(frames);
+        self.buffer.drain(..used * self.channels);
+        self.position -= used as f64;
+        output
+    }
+}
+
+/// Blackman-windowed sinc low-pass at `cutoff` times the input Nyquist
+/// frequency, `t` input samples from the centre.
+fn kernel(t: f64, cutoff: f64, half: f64) -> f64 {
+    if t.abs() >= half {
+        return 0.;
+    }
+
+    let x = PI * cutoff * t;
+    let sinc = if x == 0. { 1. } else { x.sin() / x };
+    let window = 0.42 + 0.5 * (PI * t / half).cos() + 0.08 * (2. * PI * t / half).cos();
+
+    cutoff * sinc * window
+}
+
+/// Chunks passed through a `Resampler`, see `Resampler::stream`.
+pub struct Resampled<I> {
+    chunks: I,
+    resampler: Resampler,
+    flushed: bool,
+}
+
+impl<I: Iterator<Item = Vec<f32>>> Iterator for Resampled<I> {
+    type Item = Vec<f32>;
+
+    fn next(&mut self) -> Option<Vec<f32>> {
+        match self.chunks.next() {
+            Some(chunk) => Some(self.resampler.process(&chunk)),
+            None if !self.flushed => {
+                self.flushed = true;
+                Some(self.resampler.flush()).filter(|tail| !tail.is_empty())
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sine_keeps_its_pitch() {
+        let sine = |rate: u32, i: usize| (2. * PI * 1000. * i as f64 / rate as f64).sin() as f32;
+        let input: Vec<f32> = (0..44100).map(|i| sine(44100, i)).collect();
+        let mut resampler = Resampler::new(44100, 48000, 1);
+        let mut output: Vec<f32> = input
+            .chunks(1000)
+            .flat_map(|chunk| resampler.process(chunk))
+            .collect();
+        output.extend(resampler.flush());
+
+        assert_eq!(output.len(), 48000);
+        for (i, sample) in output.iter().enumerate().skip(100).take(47800) {
+            assert!((sample - sine(48000, i)).abs() < 1e-3, "{i}: {sample}");
+        }
+    }
+
+    #[test]
+    fn steep_downsampling_keeps_the_filter_short() {
+        let mut resampler = Resampler::new(48000, 1, 1);
+        let mut output = resampler.process(&vec![0.5; 48000 * 3]);
+        output.extend(resampler.flush());
+
+        assert_eq!(resampler.half, MAX_HALF);
+        assert_eq!(resampler.table.len(), (PHASES + 1) * 2 * MAX_HALF);
+        assert_eq!(output.len(), 3);
+    }
+}
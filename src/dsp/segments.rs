@@ -0,0 +1,142 @@
+//! Splitting recordings into segments separated by silence.
+use std::io::{self, Write};
+
+use super::StftGrid;
+
+/// Length of the blocks whose level is compared to the threshold.
+const BLOCK_SECONDS: f32 = 0.01;
+
+#[derive(Copy, Clone, Debug)]
+pub struct SilenceParams {
+    /// RMS level in dBFS below which silence starts
+    pub threshold_db: f32,
+    /// Rise above the threshold needed to end silence
+    pub hysteresis_db: f32,
+    /// Shorter silences don't split segments
+    pub min_silence: f32,
+    /// Shorter segments are dropped
+    pub min_segment: f32,
+}
+
+impl Default for SilenceParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: -50.,
+            hysteresis_db: 6.,
+            min_silence: 0.5,
+            min_segment: 1.,
+        }
+    }
+}
+
+/// A span of sound between silences, in samples.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Segment {
+    /// Start and end in seconds within the file.
+    pub fn seconds(&self, grid: &StftGrid) -> (f64, f64) {
+        (
+            grid.sample_seconds(self.start),
+            grid.sample_seconds(self.end),
+        )
+    }
+}
+
+/// Non-silent segments of a signal.
+pub fn segments(signal: &[f32], sample_rate: u32, params: &SilenceParams) -> Vec<Segment> {
+    let block = ((BLOCK_SECONDS * sample_rate as f32) as usize).max(1);
+    let min_silence = (params.min_silence * sample_rate as f32) as usize;
+    let min_segment = (params.min_segment * sample_rate as f32) as usize;
+    let mut silences: Vec<(usize, usize)> = Vec::new();
+    let mut silent_since = Some(0);
+
+    for (i, chunk) in signal.chunks(block).enumerate() {
+        let power = chunk.iter().map(|x| x * x).sum::<f32>() / chunk.len() as f32;
+        let level = 10. * power.log10();
+
+        match silent_since {
+            Some(start) if level > params.threshold_db + params.hysteresis_db => {
+                silences.push((start, i * block));
+                silent_since = None;
+            }
+            None if level < params.threshold_db => silent_since = Some(i * block),
+            _ => {}
+        }
+    }
+    if let Some(start) = silent_since {
+        silences.push((start, signal.len()));
+    }
+
+    // Sound lies between the silences long enough to split on, and the
+    // recording may start or end with sound.
+    let mut bounds = vec![0];
+    silences
+        .iter()
+        .filter(|(start, end)| end - start >= min_silence || *start == 0 || *end == signal.len())
+        .for_each(|(start, end)| bounds.extend([*start, *end]));
+    bounds.push(signal.len());
+
+    bounds
+        .chunks(2)
+        .map(|pair| Segment {
+            start: pair[0],
+            end: pair[1],
+        })
+        .filter(|segment| segment.end > segment.start && segment.end - segment.start >= min_segment)
+        .collect()
+}
+
+/// Write segments as tab separated start, end and label lines, the format
+/// of Audacity label tracks.
+pub fn write_labels(
+    segments: &[Segment],
+    grid: &StftGrid,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    for (i, segment) in segments.iter().enumerate() {
+        let (start, end) = segment.seconds(grid);
+        writeln!(writer, "{start:.6}\t{end:.6}\t{}", i + 1)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_long_silence() {
+        let tone =
+            |seconds: f32| (0..(seconds * 1000.) as usize).map(|i| (i as f32 * 0.3).sin() * 0.5);
+        let silence = |seconds: f32| std::iter::repeat_n(0., (seconds * 1000.) as usize);
+        let signal: Vec<f32> = silence(0.3)
+            .chain(tone(2.))
+            .chain(silence(0.2))
+            .chain(tone(2.))
+            .chain(silence(1.))
+            .chain(tone(0.5))
+            .chain(silence(1.))
+            .chain(tone(3.))
+            .collect();
+        let segments = segments(&signal, 1000, &SilenceParams::default());
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    start: 300,
+                    end: 4500
+                },
+                Segment {
+                    start: 7000,
+                    end: 10000
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Whole-file spectra aggregated from the STFT frames.
+
+/// Long-term average spectrum, averaging power across frames.
+pub fn long_term_average(frames: &[Vec<f32>]) -> Vec<f32> {
+    let bins = frames.first().map(|frame| frame.len()).unwrap_or(0);
+    let mut power = vec![0f64; bins];
+
+    for frame in frames {
+        for (sum, level) in power.iter_mut().zip(frame) {
+            *sum += 10f64.powf(*level as f64 / 10.);
+        }
+    }
+
+    power
+        .iter()
+        .map(|sum| (10. * (sum / frames.len().max(1) as f64).log10()) as f32)
+        .collect()
+}
+
+/// Loudest level of each bin across frames.
+pub fn max_hold(frames: &[Vec<f32>]) -> Vec<f32> {
+    let bins = frames.first().map(|frame| frame.len()).unwrap_or(0);
+
+    frames
+        .iter()
+        .fold(vec![f32::NEG_INFINITY; bins], |mut max, frame| {
+            max.iter_mut()
+                .zip(frame)
+                .for_each(|(max, level)| *max = max.max(*level));
+            max
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_and_empty() {
+        let frames = vec![vec![-20., -60.]; 5];
+
+        assert_eq!(long_term_average(&frames), vec![-20., -60.]);
+        assert_eq!(max_hold(&frames), vec![-20., -60.]);
+        assert!(long_term_average(&[]).is_empty());
+        assert!(max_hold(&[]).is_empty());
+    }
+
+    #[test]
+    fn average_is_of_power() {
+        let frames = vec![vec![0.], vec![f32::NEG_INFINITY]];
+
+        assert!((long_term_average(&frames)[0] + 3.0103).abs() < 1e-3);
+        assert_eq!(max_hold(&frames), vec![0.]);
+    }
+}
@@ -0,0 +1,103 @@
+//! Exponential sine sweeps and impulse response deconvolution (Farina).
+use std::f64::consts::PI;
+
+use crate::fft::convolve;
+
+/// Length of the raised-cosine fades at both ends of the sweep.
+const FADE_SECONDS: f64 = 0.01;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Sweep {
+    pub start_hz: f64,
+    pub end_hz: f64,
+    pub seconds: f64,
+    pub sample_rate: u32,
+}
+
+impl Sweep {
+    /// Time constant of the exponential frequency glide.
+    fn rate(&self) -> f64 {
+        self.seconds / (self.end_hz / self.start_hz).ln()
+    }
+
+    fn len(&self) -> usize {
+        (self.seconds * self.sample_rate as f64).round() as usize
+    }
+
+    /// The sweep signal with short fades against clicks.
+    pub fn signal(&self) -> Vec<f32> {
+        let rate = self.rate();
+        let len = self.len();
+        let fade = (FADE_SECONDS * self.sample_rate as f64) as usize;
+
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / self.sample_rate as f64;
+                let phase = 2. * PI * self.start_hz * rate * ((t / rate).exp() - 1.);
+                let edge = i.min(len - 1 - i);
+                let gain = if edge < fade {
+                    0.5 - 0.5 * (PI * edge as f64 / fade as f64).cos()
+                } else {
+                    1.
+                };
+
+                (gain * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    /// Time-reversed sweep with a decaying envelope, flattening the pink
+    /// spectrum of the sweep, scaled to deconvolve to a unit impulse.
+    pub fn inverse_filter(&self) -> Vec<f32> {
+        let rate = self.rate();
+        let signal = self.signal();
+        let mut inverse: Vec<f32> = signal
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, x)| x * (-(i as f64) / self.sample_rate as f64 / rate).exp() as f32)
+            .collect();
+        let peak = convolve(&signal, &inverse)
+            .iter()
+            .fold(0f32, |peak, x| peak.max(x.abs()));
+
+        inverse.iter_mut().for_each(|x| *x /= peak);
+        inverse
+    }
+
+    /// Deconvolve a recorded response into the linear impulse response,
+    /// dropping the harmonic distortion products that arrive before it.
+    pub fn impulse_response(&self, recording: &[f32]) -> Vec<f32> {
+        let inverse = self.inverse_filter();
+        let mut response = convolve(recording, &inverse);
+
+        response.drain(..(inverse.len() - 1).min(response.len()));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_deconvolves_to_impulse() {
+        let sweep = Sweep {
+            start_hz: 20.,
+            end_hz: 20000.,
+            seconds: 1.,
+            sample_rate: 48000,
+        };
+        let mut recording = vec![0.; 100];
+        recording.extend(sweep.signal().iter().map(|x| x * 0.5));
+        let response = sweep.impulse_response(&recording);
+        let (peak, level) = response
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .unwrap();
+
+        assert_eq!(peak, 100);
+        assert!((level - 0.5).abs() < 0.01, "{level}");
+    }
+}
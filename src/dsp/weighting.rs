@@ -0,0 +1,86 @@
+//! Frequency weighting curves for display and level metrics.
+use rustfft::num_complex::Complex;
+use strum_macros::{Display, EnumIter};
+
+use super::StftGrid;
+
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq, clap::ValueEnum)]
+pub enum Weighting {
+    /// Flat response
+    #[default]
+    Z,
+    /// IEC 61672 A-weighting
+    A,
+    /// IEC 61672 C-weighting
+    C,
+    /// ITU-R BS.1770 K-weighting
+    K,
+}
+
+impl Weighting {
+    /// Gain in decibels at a frequency in Hz.
+    pub fn gain_db(&self, hz: f32) -> f32 {
+        let f2 = (hz as f64).powi(2);
+
+        let gain = match self {
+            Self::Z => 0.,
+            Self::A => {
+                let r = 12194f64.powi(2) * f2 * f2
+                    / ((f2 + 20.6f64.powi(2))
+                        * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+                        * (f2 + 12194f64.powi(2)));
+                20. * r.log10() + 2.0
+            }
+            Self::C => {
+                let r = 12194f64.powi(2) * f2 / ((f2 + 20.6f64.powi(2)) * (f2 + 12194f64.powi(2)));
+                20. * r.log10() + 0.06
+            }
+            Self::K => k_weighting_db(hz as f64),
+        };
+
+        gain as f32
+    }
+
+    /// Gain in decibels of each STFT bin.
+    pub fn gains(&self, grid: &StftGrid) -> Vec<f32> {
+        (0..grid.window_size / 2 + 1)
+            .map(|bin| self.gain_db(grid.bin_hz(bin as f32)))
+            .collect()
+    }
+}
+
+/// Response of the two BS.1770 biquads, specified at 48 kHz, evaluated up
+/// to their Nyquist frequency.
+fn k_weighting_db(hz: f64) -> f64 {
+    const SHELF: ([f64; 3], [f64; 3]) = (
+        [1.53512485958697, -2.69169618940638, 1.19839281085285],
+        [1.0, -1.69065929318241, 0.73248077421585],
+    );
+    const HIGH_PASS: ([f64; 3], [f64; 3]) =
+        ([1.0, -2.0, 1.0], [1.0, -1.99004745483398, 0.99007225036621]);
+
+    let omega = 2. * std::f64::consts::PI * hz.min(24000.) / 48000.;
+    let z1 = Complex::from_polar(1., -omega);
+    let z2 = z1 * z1;
+    let response = |(b, a): ([f64; 3], [f64; 3])| {
+        ((b[0] + b[1] * z1 + b[2] * z2) / (a[0] + a[1] * z1 + a[2] * z2)).norm()
+    };
+
+    20. * (response(SHELF) * response(HIGH_PASS)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn reference_points() {
+        assert_float_eq!(Weighting::A.gain_db(1000.), 0., abs <= 0.01);
+        assert_float_eq!(Weighting::A.gain_db(100.), -19.1, abs <= 0.1);
+        assert_float_eq!(Weighting::C.gain_db(1000.), 0., abs <= 0.01);
+        assert_float_eq!(Weighting::C.gain_db(31.5), -3.0, abs <= 0.1);
+        assert_float_eq!(Weighting::K.gain_db(10000.), 4.0, abs <= 0.2);
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+
+use symphonia::core::errors::Error as SymphoniaError;
+
+/// Failures worth telling the user about instead of panicking.
+#[derive(Debug)]
+pub enum PhonolyzeError {
+    /// No demuxer or decoder can read the file
+    UnsupportedFormat(String),
+    /// The file has no audio track
+    MissingTrack,
+    /// The track doesn't say its sample rate
+    MissingSampleRate,
+    /// The track doesn't say how many channels it has
+    MissingChannels,
+    /// A packet couldn't be demuxed or decoded
+    Decode(String),
+    Io(std::io::Error),
+    Image(image::ImageError),
+    /// No usable graphics adapter, device or surface
+    GpuAdapter(String),
+    /// The window or its event loop couldn't be created
+    Window(String),
+    /// No usable audio output device or stream
+    AudioOutput(String),
+    /// Fewer samples to analyze than one window of this size
+    TooShort(usize),
+}
+
+impl fmt::Display for PhonolyzeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(what) => write!(f, "Unsupported format: {what}"),
+            Self::MissingTrack => write!(f, "No audio track found"),
+            Self::MissingSampleRate => write!(f, "Audio track has no sample rate"),
+            Self::MissingChannels => write!(f, "Audio track has no channel layout"),
+            Self::Decode(what) => write!(f, "Decode error: {what}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Image(e) => write!(f, "{e}"),
+            Self::GpuAdapter(what) => write!(f, "Graphics unavailable: {what}"),
+            Self::Window(what) => write!(f, "Window unavailable: {what}"),
+            Self::AudioOutput(what) => write!(f, "Audio output unavailable: {what}"),
+            Self::TooShort(window) => {
+                write!(
+                    f,
+                    "Nothing to analyze: less audio than one window of {window} samples"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PhonolyzeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Image(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PhonolyzeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<image::ImageError> for PhonolyzeError {
+    fn from(e: image::ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<SymphoniaError> for PhonolyzeError {
+    fn from(e: SymphoniaError) -> Self {
+        match e {
+            SymphoniaError::IoError(e) => Self::Io(e),
+            SymphoniaError::Unsupported(what) => Self::UnsupportedFormat(what.to_string()),
+            e => Self::Decode(e.to_string()),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, PhonolyzeError>;
@@ -5,7 +5,7 @@ use winit::{
     window::Window,
 };
 
-use crate::render::RenderView;
+use crate::{render::RenderView, worker::UserEvent};
 
 pub struct EventHandler<'a> {
     pub window: &'a Window,
@@ -22,7 +22,11 @@ impl<'a> EventHandler<'a> {
         }
     }
 
-    pub fn handle_event(&mut self, event: Event<()>, elwt: &EventLoopWindowTarget<()>) {
+    pub fn handle_event(
+        &mut self,
+        event: Event<UserEvent>,
+        elwt: &EventLoopWindowTarget<UserEvent>,
+    ) {
         if let Event::WindowEvent {
             ref event,
             window_id,
@@ -66,6 +70,17 @@ impl<'a> EventHandler<'a> {
                 self.render_view.state.modifiers = mods.state();
             }
 
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                window_id,
+            } if window_id == self.window.id() => {
+                let size = self.window.inner_size();
+                self.render_view.state.cursor = Some([
+                    position.x as f32 / size.width as f32,
+                    1.0 - position.y as f32 / size.height as f32,
+                ]);
+            }
+
             Event::WindowEvent {
                 event:
                     WindowEvent::ScaleFactorChanged {
@@ -102,6 +117,22 @@ impl<'a> EventHandler<'a> {
                 self.window.request_redraw();
             }
 
+            Event::UserEvent(UserEvent::Frames(frames)) => {
+                if let Some(spectrogram) = &mut self.render_view.state.spectrogram {
+                    spectrogram.frames.extend(frames);
+                }
+                self.window.request_redraw();
+            }
+
+            Event::UserEvent(UserEvent::CorruptPackets(seconds)) => {
+                self.render_view.state.add_corrupt_packets(&seconds);
+            }
+
+            Event::UserEvent(UserEvent::AnalysisDone) => {
+                self.render_view.state.analysis_frames = None;
+                self.window.request_redraw();
+            }
+
             Event::AboutToWait => {
                 let now = instant::Instant::now();
                 let delta = now - self.last_updated;
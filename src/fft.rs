@@ -1,4 +1,4 @@
-use std::f32::consts::PI;
+use std::{collections::VecDeque, f32::consts::PI};
 
 //use ordered_float::OrderedFloat;
 use rustfft::num_complex::Complex;
@@ -44,6 +44,50 @@ pub fn stft(
     (magnitudes, phases)
 }
 
+/// STFT of a signal arriving in chunks, keeping no more than one window of
+/// it.  Emits the same frames as `stft` over the concatenated chunks.
+#[derive(Debug)]
+pub struct StreamingStft {
+    window: Vec<f32>,
+    hop_size: usize,
+    ring: VecDeque<f32>,
+    /// Samples still to drop when the hop is longer than the window
+    skip: usize,
+}
+
+impl StreamingStft {
+    pub fn new(window: &'static str, window_size: usize, hop_size: usize) -> Self {
+        StreamingStft {
+            window: get_window(window, window_size),
+            hop_size,
+            ring: VecDeque::with_capacity(window_size),
+            skip: 0,
+        }
+    }
+
+    /// Add samples, returning the magnitudes of the frames they complete.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let window_size = self.window.len();
+        let mut frames = Vec::new();
+
+        for sample in samples {
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+
+            self.ring.push_back(*sample);
+            if self.ring.len() == window_size {
+                frames.push(dft(self.ring.make_contiguous(), &self.window).0);
+                self.ring.drain(..self.hop_size.min(window_size));
+                self.skip = self.hop_size.saturating_sub(window_size);
+            }
+        }
+
+        frames
+    }
+}
+
 fn dft(signal: &[f32], window: &[f32]) -> (Vec<f32>, Vec<f32>) {
     //dbg!(window.len());
     let _sum: f32 = window.iter().sum();
@@ -107,3 +151,50 @@ fn dft(signal: &[f32], window: &[f32]) -> (Vec<f32>, Vec<f32>) {
 
     (positive_side.to_vec(), vec![])
 }
+
+/// Linear convolution through the FFT.
+pub fn convolve(a: &[f32], b: &[f32]) -> Vec<f32> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let len = a.len() + b.len() - 1;
+    let size = len.next_power_of_two();
+    let mut planner = rustfft::FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+    let spectrum = |x: &[f32]| {
+        let mut buffer: Vec<Complex<f32>> =
+            x.iter().map(|re| Complex { re: *re, im: 0. }).collect();
+        buffer.resize(size, Complex { re: 0., im: 0. });
+        forward.process(&mut buffer);
+        buffer
+    };
+
+    let mut product: Vec<Complex<f32>> = spectrum(a)
+        .iter()
+        .zip(spectrum(b))
+        .map(|(x, y)| x * y)
+        .collect();
+    inverse.process(&mut product);
+
+    product[..len].iter().map(|x| x.re / size as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_matches_stft() {
+        let signal: Vec<f32> = (0..5000).map(|i| (i as f32 * 0.07).sin()).collect();
+        let (expected, _) = stft(&signal, "hamming", 256, 100);
+        let mut streaming = StreamingStft::new("hamming", 256, 100);
+        let frames: Vec<Vec<f32>> = signal
+            .chunks(333)
+            .flat_map(|chunk| streaming.push(chunk))
+            .collect();
+
+        assert_eq!(frames, expected);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{
+    dsp::activity::Activity,
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+/// Bottom of the strip in normalized analysis coordinates.
+const STRIP_BOTTOM: f32 = 0.97;
+const SPEECH_COLOR: [f32; 4] = [0.3, 1.0, 0.4, 0.8];
+const MUSIC_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 0.8];
+
+/// Strip along the top of the spectrogram coloured by frame class, speech
+/// in green and music in blue.
+#[derive(Debug)]
+pub struct ActivityPass {
+    overlay: OverlayPass,
+}
+
+impl ActivityPass {
+    pub fn new(activity: &[Activity], ctx: &RenderView) -> Self {
+        let mut geometry = Geometry::default();
+        let width = (activity.len() as f32 - 1.).max(1.);
+        let mut start = 0;
+
+        // One rectangle per run of frames in the same class
+        for end in 1..=activity.len() {
+            if end < activity.len() && activity[end] == activity[start] {
+                continue;
+            }
+
+            let color = match activity[start] {
+                Activity::Inactive => None,
+                Activity::Speech => Some(SPEECH_COLOR),
+                Activity::Music => Some(MUSIC_COLOR),
+            };
+            if let Some(color) = color {
+                geometry.rect(
+                    [(start as f32 - 0.5) / width, STRIP_BOTTOM],
+                    [(end as f32 - 0.5) / width, 1.],
+                    color,
+                );
+            }
+            start = end;
+        }
+
+        ActivityPass {
+            overlay: OverlayPass::new("ActivityPass", &geometry, Space::Analysis, ctx),
+        }
+    }
+}
+
+impl Layer for ActivityPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.activity == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
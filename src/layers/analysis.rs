@@ -6,12 +6,13 @@ use winit::{
 };
 
 use crate::{
+    dsp::{normalize::DisplayMode, weighting::Weighting},
     render::{RenderView, Renderer},
     uniforms::Camera,
-    uniforms::Gradient,
+    uniforms::{ColorMap, Gradient},
 };
 
-use super::{Layer, LayerMode, LayerState};
+use super::{Layer, LayerMode, LayerState, View};
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -39,10 +40,20 @@ pub struct AnalysisLayerPass {
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     num_indices: u32,
+    /// Frames the buffers have room for
+    width: usize,
+    height: usize,
+    /// Frames written so far
+    columns: usize,
     pipeline: wgpu::RenderPipeline,
     bind_group: wgpu::BindGroup,
     gradient: Gradient,
-    camera: Camera,
+    weighting: Weighting,
+    display_mode: DisplayMode,
+    color_map: Option<ColorMap>,
+    view: View,
+    /// Index of the track in `LayerState::tracks` last written
+    track: usize,
     used: bool,
 }
 
@@ -52,9 +63,25 @@ impl AnalysisLayerPass {
         ctx: &RenderView,
         layer_mode: LayerMode,
         gradient: Gradient,
+    ) -> Self {
+        let bins = analysis.first().map_or(0, Vec::len);
+        let mut pass = Self::with_capacity(analysis.len(), bins, ctx, layer_mode, gradient);
+
+        pass.append(analysis, &[], &ctx.queue);
+        pass
+    }
+
+    /// An empty pass with room for `width` frames of `height` bins, filled in
+    /// by `append`.
+    pub fn with_capacity(
+        width: usize,
+        height: usize,
+        ctx: &RenderView,
+        layer_mode: LayerMode,
+        gradient: Gradient,
     ) -> Self {
         let label = Some("AnalysisPass");
-        let (vertex_buffer, index_buffer, num_indices) = tessellate(analysis, &ctx.device);
+        let (vertex_buffer, index_buffer) = tessellate(width, height, &ctx.device);
         let shader = ctx
             .device
             .create_shader_module(wgpu::include_wgsl!("analysis.wgsl"));
@@ -106,7 +133,11 @@ impl AnalysisLayerPass {
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
             });
-        let camera = Camera::new(&ctx.device);
+        let camera = ctx
+            .state
+            .camera
+            .as_ref()
+            .expect("Camera is created with the render view");
         let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label,
             layout: &bind_group_layout,
@@ -133,75 +164,111 @@ impl AnalysisLayerPass {
         AnalysisLayerPass {
             vertex_buffer,
             index_buffer,
-            num_indices,
+            num_indices: 0,
+            width,
+            height,
+            columns: 0,
             pipeline,
             layer_mode,
             gradient,
             bind_group,
-            camera,
+            weighting: Weighting::Z,
+            display_mode: DisplayMode::Absolute,
+            color_map: None,
+            view: View::Spectrogram,
+            track: 0,
             used: false,
         }
     }
+
+    /// Write frames after those already written, dropping any beyond the
+    /// capacity.
+    fn append(&mut self, analysis: &[Vec<f32>], gains: &[f32], queue: &wgpu::Queue) {
+        let analysis = &analysis[..analysis.len().min(self.width - self.columns)];
+        let vertices = vertices(analysis, self.columns, self.width, gains);
+        let offset = self.columns * self.height * std::mem::size_of::<Vertex>();
+
+        queue.write_buffer(
+            &self.vertex_buffer,
+            offset as wgpu::BufferAddress,
+            bytemuck::cast_slice(&vertices),
+        );
+        self.columns += analysis.len();
+        self.num_indices =
+            (self.columns.saturating_sub(1) * self.height.saturating_sub(1) * 6) as u32;
+    }
+
+    /// Show this analysis in another view instead of the spectrogram.  Only
+    /// the spectrogram follows weighting and display mode changes.
+    pub fn with_view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
 }
 
-fn tessellate(
-    analysis: &Vec<Vec<f32>>,
-    device: &wgpu::Device,
-) -> (wgpu::Buffer, wgpu::Buffer, u32) {
-    let mut vertices = Vec::new();
-    let mut indices: Vec<u32> = vec![];
-    let width = analysis.len();
-    let height = analysis[0].len();
+/// Vertices of the frames starting at column `first` of a grid `width`
+/// frames wide.
+fn vertices(analysis: &[Vec<f32>], first: usize, width: usize, gains: &[f32]) -> Vec<Vertex> {
+    let Some(height) = analysis.first().map(|frame| frame.len()) else {
+        return vec![];
+    };
 
     analysis
         .iter()
-        .take(width)
         .enumerate()
-        .for_each(|(i, col)| {
-            col.iter().take(height).enumerate().for_each(|(j, level)| {
+        .map(|(i, col)| (first + i, col))
+        .flat_map(|(i, col)| {
+            col.iter().take(height).enumerate().map(move |(j, level)| {
                 use crate::uniforms::NormDb;
-                let vertex = Vertex {
+                let gain = gains.get(j).copied().unwrap_or(0.0);
+
+                Vertex {
                     position: [
                         (i as f32 / (width as f32 - 1.0)),
                         (j as f32 / (height as f32 - 1.0)),
-                        level.normalize_decibels(),
+                        (level + gain).normalize_decibels(),
                         0.0,
                     ],
-                };
-
-                vertices.push(vertex);
-
-                if i < width - 1 && j < height - 1 {
-                    let bottom_left = (height * i + j) as u32;
-                    let bottom_right = bottom_left + height as u32;
-                    let top_left = bottom_left + 1;
-                    let top_right = bottom_right + 1;
-
-                    indices.extend_from_slice(
-                        &[
-                            [top_left, bottom_left, top_right],
-                            [top_right, bottom_left, bottom_right],
-                        ]
-                        .concat(),
-                    );
                 }
             })
-        });
+        })
+        .collect()
+}
+
+fn tessellate(width: usize, height: usize, device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer) {
+    let vertices = vec![Vertex::default(); width * height];
+    let mut indices: Vec<u32> = vec![];
+
+    for i in 0..width.saturating_sub(1) {
+        for j in 0..height.saturating_sub(1) {
+            let bottom_left = (height * i + j) as u32;
+            let bottom_right = bottom_left + height as u32;
+            let top_left = bottom_left + 1;
+            let top_right = bottom_right + 1;
+
+            indices.extend_from_slice(
+                &[
+                    [top_left, bottom_left, top_right],
+                    [top_right, bottom_left, bottom_right],
+                ]
+                .concat(),
+            );
+        }
+    }
 
     let label = Some("Update Analysis");
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label,
         contents: bytemuck::cast_slice(&vertices),
-        usage: wgpu::BufferUsages::VERTEX,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
     });
     let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label,
         contents: bytemuck::cast_slice(&indices),
         usage: wgpu::BufferUsages::INDEX,
     });
-    let num_indices = indices.len() as u32;
 
-    (vertex_buffer, index_buffer, num_indices)
+    (vertex_buffer, index_buffer)
 }
 
 impl Layer for AnalysisLayerPass {
@@ -220,46 +287,48 @@ impl Layer for AnalysisLayerPass {
         state: &mut LayerState,
         _window: &winit::window::Window,
     ) -> egui_winit::EventResponse {
-        if let WindowEvent::KeyboardInput { event, .. } = event {
+        if let (WindowEvent::KeyboardInput { event, .. }, Some(camera)) =
+            (event, state.camera.as_mut())
+        {
             match event.logical_key.as_ref() {
                 Key::Named(NamedKey::ArrowLeft) | Key::Character("H") => {
                     if state.modifiers.shift_key() {
-                        self.camera.move_x(-0.03, queue);
+                        camera.move_x(-0.03, queue);
                     } else {
-                        self.camera.scale_x(-0.01, queue);
+                        camera.scale_x(-0.01, queue);
                     }
                 }
 
                 Key::Named(NamedKey::ArrowRight) | Key::Character("L") => {
                     if state.modifiers.shift_key() {
-                        self.camera.move_x(0.03, queue);
+                        camera.move_x(0.03, queue);
                     } else {
-                        self.camera.scale_x(0.01, queue);
+                        camera.scale_x(0.01, queue);
                     }
                 }
 
                 Key::Named(NamedKey::ArrowDown) | Key::Character("J") => {
                     if state.modifiers.shift_key() {
-                        self.camera.move_y(-0.03, queue);
+                        camera.move_y(-0.03, queue);
                     } else {
-                        self.camera.scale_y(-0.01, queue);
+                        camera.scale_y(-0.01, queue);
                     }
                 }
 
                 Key::Named(NamedKey::ArrowUp) | Key::Character("K") => {
                     if state.modifiers.shift_key() {
-                        self.camera.move_y(0.03, queue);
+                        camera.move_y(0.03, queue);
                     } else {
-                        self.camera.scale_y(0.01, queue);
+                        camera.scale_y(0.01, queue);
                     }
                 }
 
                 Key::Character("M") if state.modifiers.super_key() => {
-                    self.camera.zero(queue);
+                    camera.zero(queue);
                 }
 
                 Key::Character("N") if state.modifiers.super_key() => {
-                    self.camera.fill(queue);
+                    camera.fill(queue);
                 }
 
                 _ => {
@@ -292,14 +361,48 @@ impl Layer for AnalysisLayerPass {
         queue: &wgpu::Queue,
         _window: &Window,
     ) {
-        if let Some(new_color_map) = state.update_color_map() {
-            self.gradient.update(new_color_map.uniform(), queue);
+        if let (View::Spectrogram, Some(spectrogram)) = (self.view, &state.spectrogram) {
+            let frames = &spectrogram.frames;
+            let gains = || state.weighting.gains(&spectrogram.grid);
+            // Relative display modes depend on every frame, so frames still
+            // arriving are shown as they are and normalized once at the end.
+            let display_mode = match state.analysis_frames {
+                Some(_) => DisplayMode::Absolute,
+                None => state.display_mode,
+            };
+            let arrived = self.columns < frames.len().min(self.width);
+            let changed = self.weighting != state.weighting
+                || self.display_mode != display_mode
+                || self.track != state.track
+                || (arrived && display_mode != DisplayMode::Absolute);
+
+            if changed {
+                self.columns = 0;
+                match display_mode {
+                    DisplayMode::Absolute => self.append(frames, &gains(), queue),
+                    mode => self.append(&mode.apply(frames, &gains()), &[], queue),
+                }
+                self.weighting = state.weighting;
+                self.display_mode = display_mode;
+                self.track = state.track;
+            } else if arrived {
+                self.append(&frames[self.columns..], &gains(), queue);
+            }
+        }
+
+        if self.color_map != Some(state.color_map) {
+            self.gradient.update(state.color_map.uniform(), queue);
             self.gradient
-                .update_gradient_texture(new_color_map.data(), queue);
+                .update_gradient_texture(state.color_map.data(), queue);
+            self.color_map = Some(state.color_map);
         }
     }
 
-    fn render(&mut self, renderer: &mut Renderer, _state: &mut LayerState) {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.view != self.view {
+            return;
+        }
+
         let mut render_pass = renderer
             .encoder
             .begin_render_pass(&wgpu::RenderPassDescriptor {
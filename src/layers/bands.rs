@@ -0,0 +1,158 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::{
+        bands::{Band, BandFilter},
+        weighting::Weighting,
+    },
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Label, LabelSet, Layer, LayerState,
+};
+
+const MIN: [f32; 2] = [0.55, 0.5];
+const MAX: [f32; 2] = [0.98, 0.9];
+const MIN_DB: f32 = -100.;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const GRID: [f32; 4] = [1.0, 1.0, 1.0, 0.12];
+const BAR_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 0.9];
+/// Most band labels that fit under the bars.
+const MAX_LABELS: usize = 12;
+
+/// Bar graph of fractional-octave band levels under the playhead.
+#[derive(Debug)]
+pub struct BandsPass {
+    overlay: OverlayPass,
+    labels: LabelSet,
+    filter: BandFilter,
+    weighting: Option<Weighting>,
+    gains: Vec<f32>,
+    frame: Option<usize>,
+}
+
+impl BandsPass {
+    pub fn new(filter: BandFilter, bands: &[Band], ctx: &RenderView) -> Self {
+        BandsPass {
+            overlay: OverlayPass::new("BandsPass", &Geometry::default(), Space::Screen, ctx),
+            labels: Self::labels(bands),
+            filter,
+            weighting: None,
+            gains: Vec::new(),
+            frame: None,
+        }
+    }
+
+    /// Mid-band frequencies under the bars and levels of the grid lines.
+    /// With too many bands to label, every few are, counting from 1 kHz so
+    /// third octaves get the octave frequencies.
+    fn labels(bands: &[Band]) -> LabelSet {
+        let width = (MAX[0] - MIN[0]) / bands.len().max(1) as f32;
+        let step = bands.len().div_ceil(MAX_LABELS).max(1);
+        let khz = bands
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                (a.center / 1000.)
+                    .ln()
+                    .abs()
+                    .total_cmp(&(b.center / 1000.).ln().abs())
+            })
+            .map_or(0, |(i, _)| i);
+        let frequencies = bands
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i.abs_diff(khz) % step == 0)
+            .map(|(i, band)| Label {
+                position: [MIN[0] + (i as f32 + 0.5) * width, MIN[1] - 0.03],
+                space: Space::Screen,
+                text: band.label(),
+            });
+        let levels = (0..5).map(|i| {
+            let db = MIN_DB * i as f32 / 5.;
+
+            Label {
+                position: [MIN[0] + 0.02, Self::y(db)],
+                space: Space::Screen,
+                text: format!("{db:.0} dB"),
+            }
+        });
+
+        LabelSet::new(frequencies.chain(levels).collect())
+    }
+
+    /// Height of a level in window coordinates.
+    fn y(db: f32) -> f32 {
+        MIN[1] + ((db - MIN_DB) / -MIN_DB).clamp(0., 1.) * (MAX[1] - MIN[1])
+    }
+
+    fn geometry(levels: &[f32]) -> Geometry {
+        let mut geometry = Geometry::default();
+        let width = (MAX[0] - MIN[0]) / levels.len().max(1) as f32;
+        let y = Self::y;
+
+        geometry.rect(MIN, MAX, BACKGROUND);
+        (1..5).for_each(|i| {
+            let db = MIN_DB * i as f32 / 5.;
+            geometry.line([MIN[0], y(db)], [MAX[0], y(db)], GRID);
+        });
+        levels.iter().enumerate().for_each(|(i, level)| {
+            let left = MIN[0] + i as f32 * width;
+            geometry.rect(
+                [left + width * 0.1, MIN[1]],
+                [left + width * 0.9, y(*level)],
+                BAR_COLOR,
+            );
+        });
+
+        geometry
+    }
+}
+
+impl Layer for BandsPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        self.labels.show(state.overlays.bands == Some(true), state);
+        if state.overlays.bands != Some(true) {
+            return;
+        }
+        let Some(spectrogram) = &state.spectrogram else {
+            return;
+        };
+        if spectrogram.frames.is_empty() {
+            return;
+        }
+
+        let frame = state
+            .playhead_frame()
+            .unwrap_or(0)
+            .min(spectrogram.frames.len() - 1);
+
+        if self.frame == Some(frame) && self.weighting == Some(state.weighting) {
+            return;
+        }
+        if self.weighting != Some(state.weighting) {
+            self.gains = state.weighting.gains(&spectrogram.grid);
+            self.weighting = Some(state.weighting);
+        }
+
+        let levels = self.filter.levels(&spectrogram.frames[frame], &self.gains);
+        self.overlay
+            .set_geometry(&Self::geometry(&levels), device, queue);
+        self.frame = Some(frame);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.bands == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
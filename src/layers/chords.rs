@@ -0,0 +1,78 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::harmony::Chord,
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Label, LabelSet, Layer, LayerState, View,
+};
+
+/// Vertical extent of the lane in normalized analysis coordinates, below
+/// the activity strip.
+const LANE_BOTTOM: f32 = 0.9;
+const LANE_TOP: f32 = 0.96;
+const LANE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const CHANGE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.6];
+
+/// Lane of chord names across the top of the spectrogram.
+#[derive(Debug)]
+pub struct ChordsPass {
+    overlay: OverlayPass,
+    labels: LabelSet,
+}
+
+impl ChordsPass {
+    pub fn new(chords: &[Chord], ctx: &RenderView) -> Self {
+        let mut geometry = Geometry::default();
+        let mut labels = Vec::new();
+        let width = (chords.len() as f32 - 1.).max(1.);
+        let mut start = 0;
+
+        geometry.rect([0., LANE_BOTTOM], [1., LANE_TOP], LANE_COLOR);
+
+        for end in 1..=chords.len() {
+            if end < chords.len() && chords[end] == chords[start] {
+                continue;
+            }
+
+            let (left, right) = ((start as f32 - 0.5) / width, (end as f32 - 0.5) / width);
+            geometry.line([left, LANE_BOTTOM], [left, LANE_TOP], CHANGE_COLOR);
+            if chords[start].0.is_some() {
+                labels.push(Label {
+                    position: [(left + right) / 2., LANE_BOTTOM],
+                    space: Space::Analysis,
+                    text: chords[start].to_string(),
+                });
+            }
+            start = end;
+        }
+
+        ChordsPass {
+            overlay: OverlayPass::new("ChordsPass", &geometry, Space::Analysis, ctx),
+            labels: LabelSet::new(labels),
+        }
+    }
+}
+
+impl Layer for ChordsPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        let visible = state.overlays.chords == Some(true) && state.view == View::Spectrogram;
+        self.labels.show(visible, state);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.chords == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
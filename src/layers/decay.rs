@@ -0,0 +1,81 @@
+use crate::{
+    dsp::decay::Decay,
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+const MIN: [f32; 2] = [0.3, 0.3];
+const MAX: [f32; 2] = [0.7, 0.7];
+const MIN_DB: f32 = -60.;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const GRID: [f32; 4] = [1.0, 1.0, 1.0, 0.12];
+
+/// Panel with the Schroeder decay curve of each band, low bands in red
+/// through high bands in blue.
+#[derive(Debug)]
+pub struct DecayPass {
+    overlay: OverlayPass,
+}
+
+impl DecayPass {
+    pub fn new(decays: &[Decay], ctx: &RenderView) -> Self {
+        DecayPass {
+            overlay: OverlayPass::new("DecayPass", &Self::geometry(decays), Space::Screen, ctx),
+        }
+    }
+
+    fn geometry(decays: &[Decay]) -> Geometry {
+        let mut geometry = Geometry::default();
+        let seconds = decays
+            .iter()
+            .map(|decay| {
+                let end = decay
+                    .curve
+                    .iter()
+                    .position(|level| *level <= MIN_DB)
+                    .unwrap_or(decay.curve.len());
+                end as f32 * decay.step_seconds
+            })
+            .fold(0f32, f32::max)
+            .max(f32::EPSILON);
+        let point = |t: f32, db: f32| {
+            [
+                MIN[0] + (t / seconds).clamp(0., 1.) * (MAX[0] - MIN[0]),
+                MIN[1] + (1. - db / MIN_DB).clamp(0., 1.) * (MAX[1] - MIN[1]),
+            ]
+        };
+
+        geometry.rect(MIN, MAX, BACKGROUND);
+        (1..6).for_each(|i| {
+            let db = MIN_DB * i as f32 / 6.;
+            geometry.line(point(0., db), point(seconds, db), GRID);
+        });
+
+        decays.iter().enumerate().for_each(|(i, decay)| {
+            let x = i as f32 / (decays.len() as f32 - 1.).max(1.);
+            let points: Vec<[f32; 2]> = decay
+                .curve
+                .iter()
+                .enumerate()
+                .take_while(|(_, level)| level.is_finite() && **level > MIN_DB)
+                .map(|(step, level)| point(step as f32 * decay.step_seconds, *level))
+                .collect();
+
+            geometry.polyline(&points, [1. - x, 0.4, x, 0.9]);
+        });
+
+        geometry
+    }
+}
+
+impl Layer for DecayPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.decay == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::distortion::{percent, Distortion},
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Label, LabelSet, Layer, LayerState,
+};
+
+const MIN: [f32; 2] = [0.25, 0.3];
+const MAX: [f32; 2] = [0.75, 0.7];
+const MIN_DB: f32 = -140.;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const GRID: [f32; 4] = [1.0, 1.0, 1.0, 0.12];
+const FUNDAMENTAL_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 0.9];
+const HARMONIC_COLOR: [f32; 4] = [1.0, 0.5, 0.2, 0.9];
+
+/// Bar chart of the fundamental and harmonic levels of a test tone, with
+/// the distortion figures as a caption.
+#[derive(Debug)]
+pub struct DistortionPass {
+    overlay: OverlayPass,
+    labels: LabelSet,
+}
+
+impl DistortionPass {
+    pub fn new(distortion: &Distortion, ctx: &RenderView) -> Self {
+        let mut geometry = Geometry::default();
+        let mut labels = Vec::new();
+        let bars = 1 + distortion.harmonics.len();
+        let width = (MAX[0] - MIN[0]) / bars as f32;
+        let y = |db: f32| MIN[1] + ((db - MIN_DB) / -MIN_DB).clamp(0., 1.) * (MAX[1] - MIN[1]);
+
+        geometry.rect(MIN, MAX, BACKGROUND);
+        (1..7).for_each(|i| {
+            let db = MIN_DB * i as f32 / 7.;
+            geometry.line([MIN[0], y(db)], [MAX[0], y(db)], GRID);
+        });
+
+        std::iter::once(&distortion.fundamental)
+            .chain(&distortion.harmonics)
+            .enumerate()
+            .for_each(|(i, harmonic)| {
+                let left = MIN[0] + i as f32 * width;
+                let color = if harmonic.number == 1 {
+                    FUNDAMENTAL_COLOR
+                } else {
+                    HARMONIC_COLOR
+                };
+
+                geometry.rect(
+                    [left + width * 0.15, MIN[1]],
+                    [left + width * 0.85, y(harmonic.level)],
+                    color,
+                );
+                labels.push(Label {
+                    position: [left + width / 2., y(harmonic.level)],
+                    space: Space::Screen,
+                    text: format!("{:.1}", harmonic.level),
+                });
+                labels.push(Label {
+                    position: [left + width / 2., MIN[1]],
+                    space: Space::Screen,
+                    text: format!("H{}", harmonic.number),
+                });
+            });
+
+        labels.push(Label {
+            position: [(MIN[0] + MAX[0]) / 2., MAX[1]],
+            space: Space::Screen,
+            text: format!(
+                "{:.1} Hz   THD {:.1} dB ({:.3} %)   THD+N {:.1} dB ({:.3} %)   SINAD {:.1} dB   SNR {:.1} dB",
+                distortion.fundamental.hz,
+                distortion.thd,
+                percent(distortion.thd),
+                distortion.thd_n,
+                percent(distortion.thd_n),
+                distortion.sinad,
+                distortion.snr,
+            ),
+        });
+
+        DistortionPass {
+            overlay: OverlayPass::new("DistortionPass", &geometry, Space::Screen, ctx),
+            labels: LabelSet::new(labels),
+        }
+    }
+}
+
+impl Layer for DistortionPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        let visible = state.overlays.distortion == Some(true);
+        self.labels.show(visible, state);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.distortion == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    dsp::StftGrid,
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+/// Half height of a dot in normalized analysis coordinates.
+const DOT_RADIUS: f32 = 0.002;
+
+const FORMANT_COLORS: [[f32; 4]; 4] = [
+    [1.0, 0.3, 0.3, 0.9],
+    [1.0, 0.8, 0.2, 0.9],
+    [0.3, 1.0, 0.4, 0.9],
+    [0.3, 0.7, 1.0, 0.9],
+];
+
+/// Formants F1 to F4 as dots over the spectrogram.
+#[derive(Debug)]
+pub struct FormantsPass {
+    overlay: OverlayPass,
+}
+
+impl FormantsPass {
+    pub fn new(formants: &[[Option<f32>; 4]], grid: &StftGrid, ctx: &RenderView) -> Self {
+        let mut geometry = Geometry::default();
+        let width = (formants.len() as f32 - 1.).max(1.);
+        let height = (grid.window_size / 2) as f32;
+
+        for (frame, formants) in formants.iter().enumerate() {
+            let x = frame as f32 / width;
+
+            for (hz, color) in formants.iter().zip(FORMANT_COLORS) {
+                if let Some(hz) = hz {
+                    let y = hz / grid.bin_hz(1.) / height;
+                    geometry.rect(
+                        [x - 0.3 / width, y - DOT_RADIUS],
+                        [x + 0.3 / width, y + DOT_RADIUS],
+                        color,
+                    );
+                }
+            }
+        }
+
+        FormantsPass {
+            overlay: OverlayPass::new("FormantsPass", &geometry, Space::Analysis, ctx),
+        }
+    }
+}
+
+impl Layer for FormantsPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.formants == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
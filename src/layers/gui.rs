@@ -1,9 +1,17 @@
 use strum::IntoEnumIterator;
 use winit::event::WindowEvent;
 
-use crate::{render::Renderer, uniforms::ColorMap};
+use crate::{
+    dsp::{normalize::DisplayMode, weighting::Weighting},
+    render::Renderer,
+    timecode::TimeFormat,
+    uniforms::ColorMap,
+    worker::UserEvent,
+};
 
-use super::{Layer, LayerState};
+use super::slice::SliceSource;
+
+use super::{overlay::Space, LabelSet, Layer, LayerState, View};
 
 pub struct Gui {
     context: egui::Context,
@@ -14,7 +22,7 @@ pub struct Gui {
 
 impl Gui {
     pub fn new(
-        event_loop: &winit::event_loop::EventLoop<()>,
+        event_loop: &winit::event_loop::EventLoop<UserEvent>,
         device: &wgpu::Device,
         texture_format: wgpu::TextureFormat,
         scale_factor: f32,
@@ -39,6 +47,33 @@ impl Gui {
     }
 }
 
+/// Paint and clear the labels queued by other layers.
+fn draw_labels(ctx: &egui::Context, state: &mut LayerState) {
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("labels"),
+    ));
+    let screen = ctx.screen_rect();
+
+    let sets = std::mem::take(&mut state.labels);
+
+    for label in sets.iter().flat_map(LabelSet::iter) {
+        let [x, y] = match (label.space, &state.camera) {
+            (Space::Screen, _) => label.position,
+            (Space::Analysis, Some(camera)) => camera.project(label.position),
+            (Space::Analysis, None) => continue,
+        };
+
+        painter.text(
+            screen.min + egui::vec2(x * screen.width(), (1. - y) * screen.height()),
+            egui::Align2::CENTER_BOTTOM,
+            &label.text,
+            egui::FontId::proportional(12.),
+            egui::Color32::WHITE,
+        );
+    }
+}
+
 impl Layer for Gui {
     fn handle_event(
         &mut self,
@@ -52,6 +87,7 @@ impl Layer for Gui {
 
     fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
         let input = self.window_state.take_egui_input(renderer.window);
+        let mut jump = None;
         let output = {
             println!(
                 "-000--------------------{:?}---{:?}--------------------",
@@ -60,6 +96,45 @@ impl Layer for Gui {
             );
             self.context.run(input, |ctx| {
                 egui::Area::new("testitout").show(ctx, |ui| {
+                    if let (Some(expected), Some(spectrogram)) =
+                        (state.analysis_frames, &state.spectrogram)
+                    {
+                        // Without a known length, only the frames so far are shown.
+                        let (done, text) = match expected {
+                            0 => (0., format!("Analysing {} frames", spectrogram.frames.len())),
+                            _ => {
+                                let done = spectrogram.frames.len() as f32 / expected as f32;
+
+                                (done, format!("Analysing {:.0}%", done * 100.))
+                            }
+                        };
+
+                        ui.add(egui::ProgressBar::new(done).desired_width(200.).text(text));
+                    }
+                    if let Some(progress) = state.progress.as_ref().and_then(|p| p.lock().ok()) {
+                        ui.label(format!(
+                            "Playhead {}",
+                            state.timecode.format(progress.position_now())
+                        ));
+                    }
+                    if let Some(seconds) = state.cursor_seconds() {
+                        ui.label(format!("Cursor {}", state.timecode.format(seconds)));
+                    }
+                    if let Some(key) = state.key {
+                        ui.label(format!("Key: {key}"));
+                    }
+                    if !state.file_info.is_empty() {
+                        egui::CollapsingHeader::new("File info").show(ui, |ui| {
+                            egui::Grid::new("file_info").show(ui, |ui| {
+                                for (label, value) in &state.file_info {
+                                    ui.label(*label);
+                                    ui.label(value);
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+
                     egui::ComboBox::from_label("Colormap")
                         .selected_text(format!("{:?}", state.color_map))
                         .show_ui(ui, |ui| {
@@ -67,10 +142,163 @@ impl Layer for Gui {
                                 ui.selectable_value(&mut state.color_map, color, color.to_string());
                             }
                         });
+
+                    egui::ComboBox::from_label("Time format")
+                        .selected_text(state.timecode.format.to_string())
+                        .show_ui(ui, |ui| {
+                            for format in TimeFormat::iter() {
+                                ui.selectable_value(
+                                    &mut state.timecode.format,
+                                    format,
+                                    format.to_string(),
+                                );
+                            }
+                        });
+                    egui::ComboBox::from_label("Weighting")
+                        .selected_text(format!("{}-weighting", state.weighting))
+                        .show_ui(ui, |ui| {
+                            for weighting in Weighting::iter() {
+                                ui.selectable_value(
+                                    &mut state.weighting,
+                                    weighting,
+                                    format!("{weighting}-weighting"),
+                                );
+                            }
+                        });
+                    egui::ComboBox::from_label("Display")
+                        .selected_text(state.display_mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for mode in DisplayMode::iter() {
+                                ui.selectable_value(
+                                    &mut state.display_mode,
+                                    mode,
+                                    mode.to_string(),
+                                );
+                            }
+                        });
+                    if state.tracks.len() > 1 {
+                        let mut track = state.track;
+
+                        // One analysis at a time.
+                        ui.add_enabled_ui(state.analysis_frames.is_none(), |ui| {
+                            egui::ComboBox::from_label("Track")
+                                .selected_text(state.tracks[track].info.to_string())
+                                .show_ui(ui, |ui| {
+                                    for (i, other) in state.tracks.iter().enumerate() {
+                                        ui.selectable_value(&mut track, i, other.info.to_string());
+                                    }
+                                });
+                        });
+                        state.select_track(track);
+                    }
+                    if !state.views.is_empty() {
+                        egui::ComboBox::from_label("View")
+                            .selected_text(state.view.to_string())
+                            .show_ui(ui, |ui| {
+                                for view in [View::Spectrogram].iter().chain(&state.views) {
+                                    ui.selectable_value(&mut state.view, *view, view.to_string());
+                                }
+                            });
+                    }
+
+                    if let Some(show) = &mut state.overlays.partials {
+                        ui.checkbox(show, "Partials");
+                    }
+                    if let Some(show) = &mut state.overlays.ltas {
+                        ui.checkbox(show, "Long-term spectrum");
+                    }
+                    if let Some(show) = &mut state.overlays.bands {
+                        ui.checkbox(show, "Octave bands");
+                    }
+                    if let Some(show) = &mut state.overlays.waveform {
+                        ui.checkbox(show, "Waveform");
+                    }
+                    if let Some(show) = &mut state.overlays.decay {
+                        ui.checkbox(show, "Decay curves");
+                    }
+                    if let Some(show) = &mut state.overlays.segments {
+                        ui.checkbox(show, "Segments");
+                    }
+                    if let Some(show) = &mut state.overlays.activity {
+                        ui.checkbox(show, "Speech and music");
+                    }
+                    if let Some(show) = &mut state.overlays.chords {
+                        ui.checkbox(show, "Chords");
+                    }
+                    if let Some(show) = &mut state.overlays.formants {
+                        ui.checkbox(show, "Formants");
+                    }
+                    if let Some(show) = &mut state.overlays.distortion {
+                        ui.checkbox(show, "Harmonic distortion");
+                    }
+                    if let Some(show) = &mut state.overlays.slice {
+                        ui.checkbox(show, "Spectrum slice");
+
+                        if *show {
+                            egui::ComboBox::from_label("Slice source")
+                                .selected_text(state.slice.source.to_string())
+                                .show_ui(ui, |ui| {
+                                    for source in SliceSource::iter() {
+                                        ui.selectable_value(
+                                            &mut state.slice.source,
+                                            source,
+                                            source.to_string(),
+                                        );
+                                    }
+                                });
+                            ui.add(
+                                egui::Slider::new(&mut state.slice.smoothing, 1..=32)
+                                    .text("Smoothing frames"),
+                            );
+                            ui.checkbox(&mut state.slice.peak_hold, "Peak hold");
+                            ui.checkbox(&mut state.slice.freeze, "Freeze snapshot");
+                            if let Some(show) = &mut state.overlays.noise_floor {
+                                ui.checkbox(show, "Noise floor");
+                            }
+                            if let Some(show) = &mut state.overlays.lpc {
+                                ui.checkbox(show, "LPC envelope");
+                            }
+                        }
+                    }
+                    if let Some(show) = &mut state.overlays.markers {
+                        ui.checkbox(show, "Markers");
+
+                        if *show {
+                            egui::CollapsingHeader::new(format!(
+                                "{} findings",
+                                state.markers.len()
+                            ))
+                            .show(ui, |ui| {
+                                egui::ScrollArea::vertical()
+                                    .max_height(200.)
+                                    .show(ui, |ui| {
+                                        for marker in &state.markers {
+                                            let text = format!(
+                                                "{}  {}",
+                                                state.timecode.format(marker.start),
+                                                marker.label
+                                            );
+
+                                            if ui.selectable_label(false, text).clicked() {
+                                                jump = Some(marker.start);
+                                            }
+                                        }
+                                    });
+                            });
+                        }
+                    }
                 });
+
+                draw_labels(ctx, state);
             })
         };
 
+        if let (Some(seconds), Some(spectrogram), Some(camera)) =
+            (jump, &state.spectrogram, &mut state.camera)
+        {
+            camera.center_x(spectrogram.seconds_x(seconds), renderer.queue);
+        }
+
         // Keep redrawing for animations.  TODO: set a timer for non-zero durations
         //if output.repaint_after.is_zero() {
         renderer.window.request_redraw();
@@ -0,0 +1,96 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::{spectrum, StftGrid},
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    plot::LogPlot,
+    LabelSet, Layer, LayerState,
+};
+
+const AVERAGE_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 1.0];
+const MAX_HOLD_COLOR: [f32; 4] = [1.0, 0.5, 0.2, 1.0];
+
+/// Panel with the long-term average and max-hold spectra of the whole file.
+#[derive(Debug)]
+pub struct LtasPass {
+    overlay: OverlayPass,
+    labels: LabelSet,
+    /// Frames the spectra were computed from
+    frames: usize,
+}
+
+impl LtasPass {
+    pub fn new(
+        average: &[f32],
+        max_hold: &[f32],
+        frames: usize,
+        grid: &StftGrid,
+        ctx: &RenderView,
+    ) -> Self {
+        LtasPass {
+            overlay: OverlayPass::new(
+                "LtasPass",
+                &geometry(average, max_hold, grid),
+                Space::Screen,
+                ctx,
+            ),
+            labels: plot(grid).labels(),
+            frames,
+        }
+    }
+}
+
+fn plot(grid: &StftGrid) -> LogPlot {
+    LogPlot::new([0.55, 0.05], [0.98, 0.45], grid)
+}
+
+fn geometry(average: &[f32], max_hold: &[f32], grid: &StftGrid) -> Geometry {
+    let plot = plot(grid);
+    let mut geometry = Geometry::default();
+
+    plot.frame(&mut geometry);
+    plot.spectrum(&mut geometry, max_hold, grid, MAX_HOLD_COLOR);
+    plot.spectrum(&mut geometry, average, grid, AVERAGE_COLOR);
+    geometry
+}
+
+impl Layer for LtasPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        self.labels.show(state.overlays.ltas == Some(true), state);
+
+        // Wait for a background analysis to finish.
+        if state.analysis_frames.is_some() {
+            return;
+        }
+
+        if let Some(spectrogram) = &state.spectrogram {
+            if spectrogram.frames.len() != self.frames {
+                let geometry = geometry(
+                    &spectrum::long_term_average(&spectrogram.frames),
+                    &spectrum::max_hold(&spectrogram.frames),
+                    &spectrogram.grid,
+                );
+
+                self.overlay.set_geometry(&geometry, device, queue);
+                self.frames = spectrogram.frames.len();
+            }
+        }
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.ltas == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::{
+        defects::{Defect, DefectKind},
+        StftGrid,
+    },
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+/// Narrowest marker, in normalized analysis coordinates.
+const MIN_WIDTH: f32 = 0.0005;
+
+/// A span of the timeline to point out, listed in the gui.
+#[derive(Clone, Debug)]
+pub struct Marker {
+    /// Start in seconds
+    pub start: f64,
+    /// End in seconds
+    pub end: f64,
+    pub label: String,
+    pub color: [f32; 4],
+}
+
+impl Marker {
+    pub fn defect(defect: &Defect, grid: &StftGrid) -> Self {
+        let (label, color) = match defect.kind {
+            DefectKind::Clipping => (
+                format!("Clipping at {:.3}", defect.value),
+                [1.0, 0.2, 0.2, 0.5],
+            ),
+            DefectKind::DcOffset => (
+                format!("DC offset of {:+.4}", defect.value),
+                [1.0, 0.9, 0.2, 0.2],
+            ),
+            DefectKind::Dropout => ("Dropout".to_string(), [0.3, 0.5, 1.0, 0.5]),
+            DefectKind::Click => (
+                format!("Click of {:.3}", defect.value),
+                [1.0, 0.3, 1.0, 0.5],
+            ),
+        };
+
+        Marker {
+            start: grid.sample_seconds(defect.start),
+            end: grid.sample_seconds(defect.end),
+            label,
+            color,
+        }
+    }
+
+    /// A packet the decoder skipped at a time in the file.
+    pub fn corrupt_packet(seconds: f64) -> Self {
+        Marker {
+            start: seconds,
+            end: seconds,
+            label: "Corrupt packet".to_string(),
+            color: [1.0, 0.5, 0.0, 0.6],
+        }
+    }
+}
+
+/// Translucent spans across the full height of the spectrogram for each of
+/// `LayerState::markers`.
+#[derive(Debug)]
+pub struct MarkersPass {
+    overlay: OverlayPass,
+    count: Option<usize>,
+}
+
+impl MarkersPass {
+    pub fn new(ctx: &RenderView) -> Self {
+        MarkersPass {
+            overlay: OverlayPass::new("MarkersPass", &Geometry::default(), Space::Analysis, ctx),
+            count: None,
+        }
+    }
+}
+
+impl Layer for MarkersPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        if self.count == Some(state.markers.len()) {
+            return;
+        }
+        let Some(spectrogram) = &state.spectrogram else {
+            return;
+        };
+
+        let mut geometry = Geometry::default();
+
+        for marker in &state.markers {
+            let start = spectrogram.seconds_x(marker.start);
+            let end = spectrogram.seconds_x(marker.end).max(start + MIN_WIDTH);
+
+            geometry.rect([start, 0.], [end, 1.], marker.color);
+            geometry.line([start, 0.], [start, 1.], marker.color);
+        }
+
+        self.overlay.set_geometry(&geometry, device, queue);
+        self.count = Some(state.markers.len());
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.markers == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
@@ -169,18 +169,14 @@ impl Layer for MeterPass {
     ) {
         if let Some(progress) = &state.progress {
             if let Ok(progress) = progress.lock() {
-                let now = Instant::now();
-                let diff = if now > progress.instant {
-                    (now - progress.instant).as_secs_f64()
-                } else {
-                    -(progress.instant - now).as_secs_f64()
-                };
-
-                let pos = progress.music_position + diff;
+                let pos = progress.position_now();
 
                 if Instant::now().duration_since(self.last_update) > Duration::from_millis(222) {
-                    self.progress
-                        .update_position(pos as f32, progress.music_length as f32, queue);
+                    self.progress.update_position(
+                        (pos - progress.music_start) as f32,
+                        progress.music_length as f32,
+                        queue,
+                    );
                     window.request_redraw();
                     self.last_update = Instant::now();
                 }
@@ -1,13 +1,42 @@
+pub mod activity;
 pub mod analysis;
+pub mod bands;
+pub mod chords;
+pub mod decay;
+pub mod distortion;
+pub mod formants;
 pub mod gui;
+pub mod ltas;
+pub mod markers;
 pub mod meter;
+pub mod modulation;
+pub mod overlay;
+pub mod partials;
+pub mod plot;
 pub mod scaled_image;
+pub mod segments;
+pub mod slice;
+pub mod tracks;
+pub mod waveform;
 
-use std::sync::{Arc, Mutex};
+use std::{
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
 
+use strum_macros::{Display, EnumIter};
 use winit::{dpi::PhysicalSize, event::WindowEvent, window::Window};
 
-use crate::{audio::PlaybackPosition, render::Renderer, uniforms::ColorMap, uniforms::Scale};
+use self::{markers::Marker, overlay::Space, slice::SliceSettings};
+
+use crate::{
+    audio::{AudioPlayer, PlaybackPosition, TrackInfo},
+    dsp::{harmony::Key, normalize::DisplayMode, weighting::Weighting, Spectrogram},
+    render::Renderer,
+    timecode::Timecode,
+    uniforms::{Camera, ColorMap, Scale},
+    worker::TrackSource,
+};
 
 #[allow(unused_variables)]
 pub trait Layer {
@@ -46,26 +75,186 @@ pub enum LayerMode {
 #[derive(Default)]
 pub struct LayerState {
     pub color_map: ColorMap,
-    pub prev_color_map: Option<ColorMap>,
     pub progress: Option<Arc<Mutex<PlaybackPosition>>>,
+    pub player: Option<AudioPlayer>,
     pub scale: Option<Scale>,
+    pub camera: Option<Camera>,
     pub modifiers: winit::keyboard::ModifiersState,
+    /// Last mouse position in normalized window coordinates
+    pub cursor: Option<[f32; 2]>,
+    pub spectrogram: Option<Spectrogram>,
+    /// Number of frames expected while the analysis is still arriving
+    pub analysis_frames: Option<usize>,
+    pub weighting: Weighting,
+    pub display_mode: DisplayMode,
+    pub view: View,
+    /// Views with a layer to show them, offered in the gui besides the
+    /// spectrogram
+    pub views: Vec<View>,
+    /// Audio tracks of the file when there are several, offered in the gui.
+    /// The frames of the selected one are moved into `spectrogram`, and
+    /// tracks not analysed yet have none.
+    pub tracks: Vec<Track>,
+    /// Index in `tracks` of the track shown in the spectrogram
+    pub track: usize,
+    /// How to analyse the other tracks when they are picked
+    pub track_source: Option<TrackSource>,
+    pub overlays: Overlays,
+    pub slice: SliceSettings,
+    /// How times are shown
+    pub timecode: Timecode,
+    /// Title, codec and the like, shown in the gui as label and value
+    pub file_info: Vec<(&'static str, String)>,
+    /// Estimated key of the whole file
+    pub key: Option<Key>,
+    /// Points of interest on the timeline
+    pub markers: Vec<Marker>,
+    /// Text queued by layers during update, drawn and cleared by the gui
+    pub labels: Vec<LabelSet>,
+}
+
+/// Which 2D analysis fills the window.  Analysis-space overlays belong to
+/// the spectrogram and are hidden in the other views.
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq)]
+pub enum View {
+    #[default]
+    Spectrogram,
+    #[strum(serialize = "Modulation spectrum")]
+    Modulation,
+    #[strum(serialize = "Stacked tracks")]
+    Tracks,
+}
+
+/// STFT frames of one audio track of the file.
+#[derive(Debug)]
+pub struct Track {
+    pub info: TrackInfo,
+    pub frames: Vec<Vec<f32>>,
+}
+
+/// Text anchored at the bottom centre to a point in overlay coordinates.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub position: [f32; 2],
+    pub space: Space,
+    pub text: String,
+}
+
+/// Fixed labels of a layer, queued for the gui without copying them.
+#[derive(Clone, Debug, Default)]
+pub struct LabelSet(Rc<[Label]>);
+
+impl LabelSet {
+    pub fn new(labels: Vec<Label>) -> Self {
+        LabelSet(labels.into())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Label> {
+        self.0.iter()
+    }
+
+    /// Queue the labels for this frame if their layer is visible.
+    pub fn show(&self, visible: bool, state: &mut LayerState) {
+        if visible && !self.is_empty() {
+            state.labels.push(self.clone());
+        }
+    }
+}
+
+/// Visibility of the optional overlays, toggled from the gui.  Overlays
+/// that haven't been added stay `None`.
+#[derive(Debug, Default)]
+pub struct Overlays {
+    pub partials: Option<bool>,
+    pub ltas: Option<bool>,
+    pub slice: Option<bool>,
+    pub bands: Option<bool>,
+    pub waveform: Option<bool>,
+    pub decay: Option<bool>,
+    pub distortion: Option<bool>,
+    pub noise_floor: Option<bool>,
+    pub markers: Option<bool>,
+    pub segments: Option<bool>,
+    pub activity: Option<bool>,
+    pub formants: Option<bool>,
+    pub lpc: Option<bool>,
+    pub chords: Option<bool>,
 }
 
 impl LayerState {
-    fn update_color_map(&mut self) -> Option<ColorMap> {
-        let mut result = None;
+    /// Warn about packets the decoder skipped and mark them on the timeline.
+    pub fn add_corrupt_packets(&mut self, seconds: &[f64]) {
+        if seconds.is_empty() {
+            return;
+        }
+
+        for seconds in seconds {
+            log::warn!("Skipped a corrupt packet at {seconds:.3} s");
+        }
+        log::warn!("Skipped {} corrupt packets", seconds.len());
+        self.overlays.markers.get_or_insert(true);
+        self.markers
+            .extend(seconds.iter().map(|s| Marker::corrupt_packet(*s)));
+    }
 
-        if let Some(prev_color_map) = self.prev_color_map {
-            if prev_color_map != self.color_map {
-                self.prev_color_map = Some(self.color_map);
-                result = self.prev_color_map;
+    /// Show another of `tracks` in the spectrogram, analysing it in the
+    /// background the first time, and carry on playing it instead.
+    /// Overlays computed at startup stay those of the first track.
+    pub fn select_track(&mut self, index: usize) {
+        let Some(spectrogram) = &mut self.spectrogram else {
+            return;
+        };
+        if index == self.track || index >= self.tracks.len() || self.analysis_frames.is_some() {
+            return;
+        }
+
+        // Keep showing the current track rather than an empty spectrogram.
+        if self.tracks[index].frames.is_empty() {
+            let Some(source) = &self.track_source else {
+                log::error!("Track {index} can't be analysed here");
+                return;
+            };
+
+            match source.spawn(index) {
+                Ok(frames) => self.analysis_frames = Some(frames),
+                Err(e) => {
+                    log::error!("Failed to analyse track {index}: {e}");
+                    return;
+                }
             }
-        } else {
-            self.prev_color_map = Some(self.color_map);
-            result = self.prev_color_map;
         }
 
-        result
+        std::mem::swap(&mut spectrogram.frames, &mut self.tracks[self.track].frames);
+        std::mem::swap(&mut spectrogram.frames, &mut self.tracks[index].frames);
+        self.track = index;
+
+        if let Some(player) = &self.player {
+            if let Err(e) = player.switch_track(index) {
+                log::error!("Failed to play track {index}: {e}");
+            }
+        }
+    }
+
+    /// Time in the file under the mouse in the spectrogram view.
+    pub fn cursor_seconds(&self) -> Option<f64> {
+        let spectrogram = self.spectrogram.as_ref()?;
+        let x = self.camera.as_ref()?.unproject(self.cursor?)[0];
+
+        if self.view != View::Spectrogram || !(0. ..=1.).contains(&x) {
+            return None;
+        }
+
+        Some(spectrogram.x_seconds(x))
+    }
+
+    /// STFT frame under the playhead.
+    pub fn playhead_frame(&self) -> Option<usize> {
+        let seconds = self.progress.as_ref()?.lock().ok()?.position_now();
+
+        Some(self.spectrogram.as_ref()?.grid.seconds_frame(seconds))
     }
 }
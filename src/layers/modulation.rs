@@ -0,0 +1,93 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::{bands::Band, modulation::ModulationSpectrum},
+    render::{RenderView, Renderer},
+    uniforms::{ColorMap, Gradient},
+};
+
+use super::{
+    analysis::AnalysisLayerPass, overlay::Space, Label, LabelSet, Layer, LayerMode, LayerState,
+    View,
+};
+
+/// Modulation depths from -60 to 0 dB are spread over the -150 to 0 dB
+/// range of the gradient.
+const DEPTH_SCALE: f32 = 2.5;
+
+const TICKS_HZ: [f32; 5] = [2., 4., 8., 16., 32.];
+
+/// Band index against modulation frequency, drawn like a spectrogram in
+/// the modulation view.
+#[derive(Debug)]
+pub struct ModulationPass {
+    analysis: AnalysisLayerPass,
+    labels: LabelSet,
+}
+
+impl ModulationPass {
+    pub fn new(spectrum: &ModulationSpectrum, bands: &[Band], ctx: &RenderView) -> Self {
+        let columns: Vec<Vec<f32>> = spectrum
+            .columns
+            .iter()
+            .map(|column| column.iter().map(|depth| depth * DEPTH_SCALE).collect())
+            .collect();
+        let width = (columns.len() as f32 - 1.).max(1.);
+        let height = (bands.len() as f32 - 1.).max(1.);
+        let mut labels: Vec<Label> = TICKS_HZ
+            .iter()
+            .map(|hz| (hz / spectrum.resolution - 1.) / width)
+            .zip(TICKS_HZ)
+            .filter(|(x, _)| (0. ..=1.).contains(x))
+            .map(|(x, hz)| Label {
+                position: [x, 0.],
+                space: Space::Analysis,
+                text: format!("{hz} Hz"),
+            })
+            .collect();
+
+        labels.extend(bands.iter().enumerate().step_by(3).map(|(i, band)| Label {
+            position: [0.04, i as f32 / height],
+            space: Space::Analysis,
+            text: format!("{} Hz", band.label()),
+        }));
+
+        let analysis = AnalysisLayerPass::new(
+            &columns,
+            ctx,
+            LayerMode::AlphaBlend,
+            Gradient::new(
+                Some("ModulationGradient"),
+                ColorMap::default().uniform(),
+                &ctx.device,
+                &ctx.queue,
+            ),
+        )
+        .with_view(View::Modulation);
+
+        ModulationPass {
+            analysis,
+            labels: LabelSet::new(labels),
+        }
+    }
+}
+
+impl Layer for ModulationPass {
+    fn update(
+        &mut self,
+        delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        window: &Window,
+    ) {
+        self.analysis.update(delta, state, device, queue, window);
+
+        let visible = state.view == View::Modulation;
+        self.labels.show(visible, state);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        self.analysis.render(renderer, state);
+    }
+}
@@ -0,0 +1,264 @@
+use wgpu::{util::DeviceExt, PrimitiveTopology};
+
+use crate::{
+    render::{RenderView, Renderer},
+    uniforms::Camera,
+};
+
+use super::{Layer, LayerState, View};
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl Vertex {
+    fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Coordinate system of overlay geometry.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Space {
+    /// Normalized spectrogram coordinates, moved by the shared camera.
+    Analysis,
+    /// Normalized window coordinates, origin at the bottom left.
+    Screen,
+}
+
+/// Colored lines and rectangles to be drawn by an `OverlayPass`.
+#[derive(Clone, Debug, Default)]
+pub struct Geometry {
+    lines: Vec<Vertex>,
+    triangles: Vec<Vertex>,
+}
+
+impl Geometry {
+    pub fn line(&mut self, from: [f32; 2], to: [f32; 2], color: [f32; 4]) {
+        self.lines.push(Vertex {
+            position: from,
+            color,
+        });
+        self.lines.push(Vertex {
+            position: to,
+            color,
+        });
+    }
+
+    pub fn polyline(&mut self, points: &[[f32; 2]], color: [f32; 4]) {
+        points.windows(2).for_each(|pair| {
+            self.line(pair[0], pair[1], color);
+        });
+    }
+
+    pub fn rect(&mut self, min: [f32; 2], max: [f32; 2], color: [f32; 4]) {
+        let corners = [
+            [min[0], min[1]],
+            [max[0], min[1]],
+            [max[0], max[1]],
+            [min[0], max[1]],
+        ];
+
+        [0, 1, 2, 0, 2, 3].iter().for_each(|i| {
+            self.triangles.push(Vertex {
+                position: corners[*i],
+                color,
+            });
+        });
+    }
+}
+
+#[derive(Debug)]
+struct VertexBuffer {
+    label: &'static str,
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    len: u32,
+}
+
+impl VertexBuffer {
+    fn new(label: &'static str, vertices: &[Vertex], device: &wgpu::Device) -> Self {
+        // Keep at least one vertex around, since empty buffers can't be bound.
+        let capacity = vertices.len().max(1);
+        let mut contents = vertices.to_vec();
+        contents.resize(capacity, Vertex::default());
+
+        VertexBuffer {
+            label,
+            buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(&contents),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }),
+            capacity,
+            len: vertices.len() as u32,
+        }
+    }
+
+    fn write(&mut self, vertices: &[Vertex], device: &wgpu::Device, queue: &wgpu::Queue) {
+        if vertices.len() > self.capacity {
+            *self = Self::new(self.label, vertices, device);
+        } else {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(vertices));
+            self.len = vertices.len() as u32;
+        }
+    }
+}
+
+/// Draws flat colored geometry on top of the other layers.
+#[derive(Debug)]
+pub struct OverlayPass {
+    lines: VertexBuffer,
+    triangles: VertexBuffer,
+    line_pipeline: wgpu::RenderPipeline,
+    triangle_pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    space: Space,
+}
+
+impl OverlayPass {
+    pub fn new(label: &'static str, geometry: &Geometry, space: Space, ctx: &RenderView) -> Self {
+        let shader = ctx
+            .device
+            .create_shader_module(wgpu::include_wgsl!("overlay.wgsl"));
+        let camera = ctx
+            .state
+            .camera
+            .as_ref()
+            .expect("Camera is created with the render view");
+        let bind_group_layout =
+            ctx.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries: &[Camera::bind_group_entry(0)],
+                });
+        let pipeline_layout = ctx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let create_pipeline = |topology: PrimitiveTopology| {
+            ctx.device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: match space {
+                            Space::Analysis => "vertex_analysis",
+                            Space::Screen => "vertex_screen",
+                        },
+                        buffers: &[Vertex::buffer_layout()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: "fragment_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: ctx.config.format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology,
+                        ..Default::default()
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+        };
+        let line_pipeline = create_pipeline(PrimitiveTopology::LineList);
+        let triangle_pipeline = create_pipeline(PrimitiveTopology::TriangleList);
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera.binding_resource(),
+            }],
+        });
+
+        OverlayPass {
+            lines: VertexBuffer::new(label, &geometry.lines, &ctx.device),
+            triangles: VertexBuffer::new(label, &geometry.triangles, &ctx.device),
+            line_pipeline,
+            triangle_pipeline,
+            bind_group,
+            space,
+        }
+    }
+
+    /// Replace the geometry, growing the vertex buffers if needed.
+    pub fn set_geometry(
+        &mut self,
+        geometry: &Geometry,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        self.lines.write(&geometry.lines, device, queue);
+        self.triangles.write(&geometry.triangles, device, queue);
+    }
+}
+
+impl Layer for OverlayPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if self.lines.len == 0 && self.triangles.len == 0 {
+            return;
+        }
+        if self.space == Space::Analysis && state.view != View::Spectrogram {
+            return;
+        }
+
+        let mut render_pass = renderer
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                occlusion_query_set: None,
+                timestamp_writes: None,
+                label: Some("Overlay"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: renderer.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        store: wgpu::StoreOp::Store,
+                        load: wgpu::LoadOp::Load,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+        if self.triangles.len > 0 {
+            render_pass.set_pipeline(&self.triangle_pipeline);
+            render_pass.set_vertex_buffer(0, self.triangles.buffer.slice(..));
+            render_pass.draw(0..self.triangles.len, 0..1);
+        }
+
+        if self.lines.len > 0 {
+            render_pass.set_pipeline(&self.line_pipeline);
+            render_pass.set_vertex_buffer(0, self.lines.buffer.slice(..));
+            render_pass.draw(0..self.lines.len, 0..1);
+        }
+    }
+}
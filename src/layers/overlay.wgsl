@@ -0,0 +1,44 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    scale: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+// Follow the spectrogram camera, with positions in analysis coordinates.
+@vertex
+fn vertex_analysis(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(
+        (in.position + camera.position) * camera.scale * 2.0 - 1.0,
+        0.0,
+        1.0,
+    );
+    out.color = in.color;
+    return out;
+}
+
+// Stay fixed on screen, with positions normalized to the window.
+@vertex
+fn vertex_screen(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(in.position * 2.0 - 1.0, 0.0, 1.0);
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
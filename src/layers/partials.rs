@@ -0,0 +1,54 @@
+use crate::{
+    dsp::partials::Track,
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+const TRACK_COLORS: [[f32; 4]; 6] = [
+    [1.0, 1.0, 1.0, 0.9],
+    [1.0, 0.8, 0.2, 0.9],
+    [0.3, 1.0, 1.0, 0.9],
+    [1.0, 0.4, 0.8, 0.9],
+    [0.6, 1.0, 0.3, 0.9],
+    [1.0, 0.5, 0.3, 0.9],
+];
+
+/// Partial tracks drawn as lines over the spectrogram.
+#[derive(Debug)]
+pub struct PartialsPass {
+    overlay: OverlayPass,
+}
+
+impl PartialsPass {
+    pub fn new(tracks: &[Track], frames: usize, bins: usize, ctx: &RenderView) -> Self {
+        let mut geometry = Geometry::default();
+        let width = (frames as f32 - 1.0).max(1.0);
+        let height = (bins as f32 - 1.0).max(1.0);
+
+        tracks.iter().enumerate().for_each(|(i, track)| {
+            let points: Vec<[f32; 2]> = track
+                .points
+                .iter()
+                .map(|point| [point.frame as f32 / width, point.bin / height])
+                .collect();
+
+            geometry.polyline(&points, TRACK_COLORS[i % TRACK_COLORS.len()]);
+        });
+
+        PartialsPass {
+            overlay: OverlayPass::new("PartialsPass", &geometry, Space::Analysis, ctx),
+        }
+    }
+}
+
+impl Layer for PartialsPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.partials == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
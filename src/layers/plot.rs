@@ -0,0 +1,138 @@
+use crate::dsp::StftGrid;
+
+use super::{
+    overlay::{Geometry, Space},
+    Label, LabelSet,
+};
+
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const MAJOR_GRID: [f32; 4] = [1.0, 1.0, 1.0, 0.35];
+const MINOR_GRID: [f32; 4] = [1.0, 1.0, 1.0, 0.12];
+
+/// Screen panel with a log frequency axis and a decibel axis.
+#[derive(Copy, Clone, Debug)]
+pub struct LogPlot {
+    /// Bottom left corner in normalized window coordinates
+    pub min: [f32; 2],
+    /// Top right corner in normalized window coordinates
+    pub max: [f32; 2],
+    pub min_hz: f32,
+    pub max_hz: f32,
+    pub min_db: f32,
+    pub max_db: f32,
+}
+
+impl LogPlot {
+    pub fn new(min: [f32; 2], max: [f32; 2], grid: &StftGrid) -> Self {
+        LogPlot {
+            min,
+            max,
+            min_hz: 20.,
+            max_hz: grid.sample_rate as f32 / 2.,
+            min_db: -120.,
+            max_db: 0.,
+        }
+    }
+
+    /// Position of a frequency and level in window coordinates.
+    pub fn point(&self, hz: f32, db: f32) -> [f32; 2] {
+        let x = (hz / self.min_hz).ln() / (self.max_hz / self.min_hz).ln();
+        let y = (db - self.min_db) / (self.max_db - self.min_db);
+
+        [
+            self.min[0] + x.clamp(0., 1.) * (self.max[0] - self.min[0]),
+            self.min[1] + y.clamp(0., 1.) * (self.max[1] - self.min[1]),
+        ]
+    }
+
+    /// Draw the background with decade and 20 dB grid lines.
+    pub fn frame(&self, geometry: &mut Geometry) {
+        geometry.rect(self.min, self.max, BACKGROUND);
+
+        let mut decade = 10f32.powf(self.min_hz.log10().floor());
+
+        while decade < self.max_hz {
+            for multiple in 1..10 {
+                let hz = decade * multiple as f32;
+
+                if hz > self.min_hz && hz < self.max_hz {
+                    let color = if multiple == 1 {
+                        MAJOR_GRID
+                    } else {
+                        MINOR_GRID
+                    };
+                    geometry.line(
+                        self.point(hz, self.min_db),
+                        self.point(hz, self.max_db),
+                        color,
+                    );
+                }
+            }
+            decade *= 10.;
+        }
+
+        let mut db = self.max_db;
+
+        while db > self.min_db {
+            geometry.line(
+                self.point(self.min_hz, db),
+                self.point(self.max_hz, db),
+                MINOR_GRID,
+            );
+            db -= 20.;
+        }
+    }
+
+    /// Tick labels of the decade lines along the bottom and of the 20 dB
+    /// lines along the left.
+    pub fn labels(&self) -> LabelSet {
+        let mut labels = Vec::new();
+        let mut decade = 10f32.powf(self.min_hz.log10().floor());
+
+        while decade < self.max_hz {
+            if decade > self.min_hz {
+                labels.push(Label {
+                    position: self.point(decade, self.min_db),
+                    space: Space::Screen,
+                    text: match decade < 1000. {
+                        true => format!("{decade:.0} Hz"),
+                        false => format!("{:.0} kHz", decade / 1000.),
+                    },
+                });
+            }
+            decade *= 10.;
+        }
+
+        let mut db = self.max_db;
+
+        while db > self.min_db {
+            labels.push(Label {
+                position: [self.min[0] + 0.02, self.point(self.min_hz, db)[1]],
+                space: Space::Screen,
+                text: format!("{db:.0} dB"),
+            });
+            db -= 20.;
+        }
+
+        LabelSet::new(labels)
+    }
+
+    /// Draw decibel levels of STFT bins as a line, skipping bins below the axis.
+    pub fn spectrum(
+        &self,
+        geometry: &mut Geometry,
+        levels: &[f32],
+        grid: &StftGrid,
+        color: [f32; 4],
+    ) {
+        let points: Vec<[f32; 2]> = levels
+            .iter()
+            .enumerate()
+            .map(|(bin, level)| (grid.bin_hz(bin as f32), *level))
+            .filter(|(hz, _)| *hz >= self.min_hz)
+            .map(|(hz, level)| self.point(hz, level))
+            .collect();
+
+        geometry.polyline(&points, color);
+    }
+}
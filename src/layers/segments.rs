@@ -0,0 +1,83 @@
+use winit::window::Window;
+
+use crate::{
+    dsp::{segments::Segment, StftGrid},
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Label, LabelSet, Layer, LayerState, View,
+};
+
+const START_COLOR: [f32; 4] = [0.3, 1.0, 0.4, 0.9];
+const END_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 0.9];
+
+/// Segment boundaries as vertical lines over the spectrogram, numbered at
+/// the top.
+#[derive(Debug)]
+pub struct SegmentsPass {
+    overlay: OverlayPass,
+    segments: Vec<Segment>,
+    grid: StftGrid,
+    labels: LabelSet,
+}
+
+impl SegmentsPass {
+    pub fn new(segments: &[Segment], grid: &StftGrid, ctx: &RenderView) -> Self {
+        SegmentsPass {
+            overlay: OverlayPass::new("SegmentsPass", &Geometry::default(), Space::Analysis, ctx),
+            segments: segments.to_vec(),
+            grid: *grid,
+            labels: LabelSet::default(),
+        }
+    }
+}
+
+impl Layer for SegmentsPass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        if state.overlays.segments != Some(true) {
+            return;
+        }
+
+        if self.labels.is_empty() && !self.segments.is_empty() {
+            let Some(spectrogram) = &state.spectrogram else {
+                return;
+            };
+            let mut geometry = Geometry::default();
+            let mut labels = Vec::new();
+
+            for (i, segment) in self.segments.iter().enumerate() {
+                let (start, end) = segment.seconds(&self.grid);
+                let (start, end) = (spectrogram.seconds_x(start), spectrogram.seconds_x(end));
+
+                geometry.line([start, 0.], [start, 1.], START_COLOR);
+                geometry.line([end, 0.], [end, 1.], END_COLOR);
+                labels.push(Label {
+                    position: [(start + end) / 2., 0.95],
+                    space: Space::Analysis,
+                    text: (i + 1).to_string(),
+                });
+            }
+
+            self.overlay.set_geometry(&geometry, device, queue);
+            self.labels = LabelSet::new(labels);
+        }
+
+        let visible = state.view == View::Spectrogram;
+        self.labels.show(visible, state);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.segments == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
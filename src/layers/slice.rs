@@ -0,0 +1,215 @@
+use strum_macros::{Display, EnumIter};
+use winit::window::Window;
+
+use crate::{
+    dsp::{lpc, spectrum, weighting::Weighting, StftGrid},
+    render::{RenderView, Renderer},
+};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    plot::LogPlot,
+    LabelSet, Layer, LayerState,
+};
+
+const LEVEL_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const PEAK_COLOR: [f32; 4] = [1.0, 0.5, 0.2, 0.9];
+const SNAPSHOT_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 0.7];
+const NOISE_COLOR: [f32; 4] = [0.8, 0.4, 1.0, 0.8];
+const LPC_COLOR: [f32; 4] = [1.0, 0.9, 0.2, 0.9];
+
+/// Which frame the spectrum slice follows.
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq)]
+pub enum SliceSource {
+    #[default]
+    Playhead,
+    Cursor,
+}
+
+/// Spectrum slice options, set from the gui.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SliceSettings {
+    pub source: SliceSource,
+    /// Keep the loudest level of each bin
+    pub peak_hold: bool,
+    /// Average power over this many frames up to the current one
+    pub smoothing: usize,
+    /// Keep a snapshot of the spectrum when enabled
+    pub freeze: bool,
+}
+
+impl Default for SliceSettings {
+    fn default() -> Self {
+        Self {
+            source: SliceSource::default(),
+            peak_hold: false,
+            smoothing: 1,
+            freeze: false,
+        }
+    }
+}
+
+/// Line graph of the spectrum under the playhead or the mouse.
+#[derive(Debug)]
+pub struct SlicePass {
+    overlay: OverlayPass,
+    plot: LogPlot,
+    labels: LabelSet,
+    frame: Option<usize>,
+    settings: Option<SliceSettings>,
+    weighting: Option<Weighting>,
+    peak: Option<Vec<f32>>,
+    snapshot: Option<Vec<f32>>,
+    noise_floor: Option<Vec<f32>>,
+    show_noise_floor: Option<bool>,
+    lpc_order: Option<usize>,
+    show_lpc: Option<bool>,
+}
+
+impl SlicePass {
+    pub fn new(grid: &StftGrid, ctx: &RenderView) -> Self {
+        let plot = LogPlot::new([0.02, 0.05], [0.45, 0.45], grid);
+
+        SlicePass {
+            overlay: OverlayPass::new("SlicePass", &Geometry::default(), Space::Screen, ctx),
+            plot,
+            labels: plot.labels(),
+            frame: None,
+            settings: None,
+            weighting: None,
+            peak: None,
+            snapshot: None,
+            noise_floor: None,
+            show_noise_floor: None,
+            lpc_order: None,
+            show_lpc: None,
+        }
+    }
+
+    /// Add a noise floor curve, toggled by `Overlays::noise_floor`.
+    pub fn with_noise_floor(mut self, noise_floor: Vec<f32>) -> Self {
+        self.noise_floor = Some(noise_floor);
+        self
+    }
+
+    /// Add an LPC envelope of the given order, toggled by `Overlays::lpc`.
+    pub fn with_lpc_envelope(mut self, order: usize) -> Self {
+        self.lpc_order = Some(order);
+        self
+    }
+
+    fn current_frame(state: &LayerState, frames: usize) -> usize {
+        let frame = match state.slice.source {
+            SliceSource::Playhead => state.playhead_frame().unwrap_or(0),
+            SliceSource::Cursor => state
+                .cursor
+                .zip(state.camera.as_ref())
+                .map(|(cursor, camera)| camera.unproject(cursor)[0])
+                .map(|x| (x.clamp(0., 1.) * (frames as f32 - 1.)).round() as usize)
+                .unwrap_or(0),
+        };
+
+        frame.min(frames - 1)
+    }
+}
+
+impl Layer for SlicePass {
+    fn update(
+        &mut self,
+        _delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _window: &Window,
+    ) {
+        self.labels.show(state.overlays.slice == Some(true), state);
+        if state.overlays.slice != Some(true) {
+            return;
+        }
+        let Some(spectrogram) = &state.spectrogram else {
+            return;
+        };
+        if spectrogram.frames.is_empty() {
+            return;
+        }
+
+        let frame = Self::current_frame(state, spectrogram.frames.len());
+
+        if self.frame == Some(frame)
+            && self.settings.as_ref() == Some(&state.slice)
+            && self.weighting == Some(state.weighting)
+            && self.show_noise_floor == state.overlays.noise_floor
+            && self.show_lpc == state.overlays.lpc
+        {
+            return;
+        }
+
+        let first = (frame + 1).saturating_sub(state.slice.smoothing.max(1));
+        let gains = state.weighting.gains(&spectrogram.grid);
+        let weighted = |levels: &[f32]| -> Vec<f32> {
+            levels
+                .iter()
+                .zip(&gains)
+                .map(|(level, gain)| level + gain)
+                .collect()
+        };
+        let levels = weighted(&spectrum::long_term_average(
+            &spectrogram.frames[first..=frame],
+        ));
+
+        match &mut self.peak {
+            Some(peak) if state.slice.peak_hold => {
+                peak.iter_mut()
+                    .zip(&levels)
+                    .for_each(|(peak, level)| *peak = peak.max(*level));
+            }
+            _ if state.slice.peak_hold => self.peak = Some(levels.clone()),
+            _ => self.peak = None,
+        }
+
+        if !state.slice.freeze {
+            self.snapshot = None;
+        } else if self.snapshot.is_none() {
+            self.snapshot = Some(levels.clone());
+        }
+
+        let grid = &spectrogram.grid;
+        let mut geometry = Geometry::default();
+
+        self.plot.frame(&mut geometry);
+        if let Some(snapshot) = &self.snapshot {
+            self.plot
+                .spectrum(&mut geometry, snapshot, grid, SNAPSHOT_COLOR);
+        }
+        if let (Some(noise_floor), Some(true)) = (&self.noise_floor, state.overlays.noise_floor) {
+            self.plot
+                .spectrum(&mut geometry, &weighted(noise_floor), grid, NOISE_COLOR);
+        }
+        if let (Some(order), Some(true)) = (self.lpc_order, state.overlays.lpc) {
+            self.plot.spectrum(
+                &mut geometry,
+                &lpc::envelope(&levels, order),
+                grid,
+                LPC_COLOR,
+            );
+        }
+        if let Some(peak) = &self.peak {
+            self.plot.spectrum(&mut geometry, peak, grid, PEAK_COLOR);
+        }
+        self.plot
+            .spectrum(&mut geometry, &levels, grid, LEVEL_COLOR);
+        self.overlay.set_geometry(&geometry, device, queue);
+
+        self.frame = Some(frame);
+        self.settings = Some(state.slice.clone());
+        self.weighting = Some(state.weighting);
+        self.show_noise_floor = state.overlays.noise_floor;
+        self.show_lpc = state.overlays.lpc;
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.slice == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use winit::window::Window;
+
+use crate::{
+    audio::TrackInfo,
+    render::{RenderView, Renderer},
+    uniforms::{ColorMap, Gradient},
+};
+
+use super::{
+    analysis::AnalysisLayerPass, overlay::Space, Label, LabelSet, Layer, LayerMode, LayerState,
+    View,
+};
+
+/// Spectrograms of several audio tracks stacked from bottom to top, the
+/// first track lowest.
+#[derive(Debug)]
+pub struct TracksPass {
+    analysis: AnalysisLayerPass,
+    labels: LabelSet,
+}
+
+impl TracksPass {
+    pub fn new(tracks: &[(&TrackInfo, &[Vec<f32>])], ctx: &RenderView) -> Self {
+        let width = tracks
+            .iter()
+            .map(|(_, frames)| frames.len())
+            .max()
+            .unwrap_or(0);
+        let bins = tracks
+            .iter()
+            .find_map(|(_, frames)| frames.first())
+            .map_or(0, |frame| frame.len());
+        // Shorter tracks are padded with silence.
+        let columns: Vec<Vec<f32>> = (0..width.max(2))
+            .map(|i| {
+                tracks
+                    .iter()
+                    .flat_map(|(_, frames)| match frames.get(i) {
+                        Some(frame) => frame.clone(),
+                        None => vec![f32::NEG_INFINITY; bins],
+                    })
+                    .collect()
+            })
+            .collect();
+        let labels = tracks
+            .iter()
+            .enumerate()
+            .map(|(i, (info, _))| Label {
+                position: [0.04, (i as f32 + 0.9) / tracks.len() as f32],
+                space: Space::Analysis,
+                text: info.to_string(),
+            })
+            .collect();
+
+        let analysis = AnalysisLayerPass::new(
+            &columns,
+            ctx,
+            LayerMode::AlphaBlend,
+            Gradient::new(
+                Some("TracksGradient"),
+                ColorMap::default().uniform(),
+                &ctx.device,
+                &ctx.queue,
+            ),
+        )
+        .with_view(View::Tracks);
+
+        TracksPass {
+            analysis,
+            labels: LabelSet::new(labels),
+        }
+    }
+}
+
+impl Layer for TracksPass {
+    fn update(
+        &mut self,
+        delta: instant::Duration,
+        state: &mut LayerState,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        window: &Window,
+    ) {
+        self.analysis.update(delta, state, device, queue, window);
+
+        let visible = state.view == View::Tracks;
+        self.labels.show(visible, state);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        self.analysis.render(renderer, state);
+    }
+}
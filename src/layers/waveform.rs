@@ -0,0 +1,66 @@
+use crate::render::{RenderView, Renderer};
+
+use super::{
+    overlay::{Geometry, OverlayPass, Space},
+    Layer, LayerState,
+};
+
+const MIN: [f32; 2] = [0.02, 0.5];
+const MAX: [f32; 2] = [0.45, 0.9];
+const COLUMNS: usize = 512;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const AXIS: [f32; 4] = [1.0, 1.0, 1.0, 0.25];
+const WAVE_COLOR: [f32; 4] = [0.4, 1.0, 0.6, 0.9];
+
+/// Panel with the min/max envelope of a signal, normalized to its peak.
+#[derive(Debug)]
+pub struct WaveformPass {
+    overlay: OverlayPass,
+}
+
+impl WaveformPass {
+    pub fn new(signal: &[f32], ctx: &RenderView) -> Self {
+        WaveformPass {
+            overlay: OverlayPass::new("WaveformPass", &Self::geometry(signal), Space::Screen, ctx),
+        }
+    }
+
+    fn geometry(signal: &[f32]) -> Geometry {
+        let mut geometry = Geometry::default();
+        let middle = (MIN[1] + MAX[1]) / 2.;
+        let half = (MAX[1] - MIN[1]) / 2.;
+        let peak = signal.iter().fold(0f32, |peak, x| peak.max(x.abs()));
+        let columns = COLUMNS.min(signal.len());
+        let width = (MAX[0] - MIN[0]) / columns.max(1) as f32;
+
+        geometry.rect(MIN, MAX, BACKGROUND);
+        geometry.line([MIN[0], middle], [MAX[0], middle], AXIS);
+
+        if peak > 0. {
+            (0..columns).for_each(|column| {
+                let chunk =
+                    &signal[column * signal.len() / columns..(column + 1) * signal.len() / columns];
+                let (low, high) = chunk
+                    .iter()
+                    .fold((0f32, 0f32), |(low, high), x| (low.min(*x), high.max(*x)));
+                let x = MIN[0] + column as f32 * width;
+
+                geometry.rect(
+                    [x, middle + low / peak * half],
+                    [x + width, middle + high / peak * half],
+                    WAVE_COLOR,
+                );
+            });
+        }
+
+        geometry
+    }
+}
+
+impl Layer for WaveformPass {
+    fn render(&mut self, renderer: &mut Renderer, state: &mut LayerState) {
+        if state.overlays.waveform == Some(true) {
+            self.overlay.render(renderer, state);
+        }
+    }
+}
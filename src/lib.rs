@@ -1,26 +1,70 @@
 //#![deny(elided_lifetimes_in_paths)]
 mod audio;
 mod color;
+mod dsp;
 mod ease;
+mod error;
 mod event;
 mod fft;
 mod layers;
+mod plot;
 mod render;
 mod resource;
+mod timecode;
 mod uniforms;
+mod worker;
 
-use clap::Parser;
+use std::{borrow::Cow, fs::File, io::BufWriter};
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use layers::meter::MeterPass;
-use winit::{event_loop::EventLoop, window::WindowBuilder};
+use winit::{event_loop::EventLoopBuilder, window::WindowBuilder};
 
 use crate::{
-    audio::AudioFile,
+    audio::{save_wav, AudioFile},
+    dsp::{
+        activity::{self, Activity},
+        bands::{self, BandFilter, Fraction},
+        decay, defects, distortion, harmony, lpc, modulation,
+        noise::{self, NoiseEstimator},
+        normalize::DisplayMode,
+        partials,
+        resample::Resampler,
+        segments::{self, SilenceParams},
+        spectrum,
+        sweep::Sweep,
+        weighting::Weighting,
+        Spectrogram, StftGrid,
+    },
+    error::PhonolyzeError,
     event::EventHandler,
-    fft::stft,
-    layers::{analysis::AnalysisLayerPass, gui::Gui, scaled_image::ScaledImagePass, LayerMode},
+    fft::{stft, StreamingStft},
+    layers::{
+        activity::ActivityPass,
+        analysis::AnalysisLayerPass,
+        bands::BandsPass,
+        chords::ChordsPass,
+        decay::DecayPass,
+        distortion::DistortionPass,
+        formants::FormantsPass,
+        gui::Gui,
+        ltas::LtasPass,
+        markers::{Marker, MarkersPass},
+        modulation::ModulationPass,
+        partials::PartialsPass,
+        scaled_image::ScaledImagePass,
+        segments::SegmentsPass,
+        slice::SlicePass,
+        tracks::TracksPass,
+        waveform::WaveformPass,
+        LayerMode, Track, View,
+    },
+    plot::Curve,
     render::RenderView,
     resource::load_image,
+    timecode::{TimeFormat, Timecode},
     uniforms::{ColorMap, Gradient},
+    worker::UserEvent,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -32,9 +76,25 @@ pub struct Cli {
     /// Song file to analyze
     #[arg(default_value = "media/sine.wav")]
     audio_file: String,
+    /// Print the audio tracks of the file and exit
+    #[arg(long, default_value_t = false)]
+    list_tracks: bool,
+    /// Audio track to analyze and play, numbered as by --list-tracks.  Give
+    /// several to stack them in one view at the analysis rate of the first
+    #[arg(long)]
+    track: Vec<usize>,
+    /// Use the embedded cover art as the background image
+    #[arg(long, default_value_t = false)]
+    cover_art: bool,
     /// Seconds to analyze
-    #[arg(short, long)]
+    #[arg(short, long, visible_alias = "duration")]
     seconds: Option<f32>,
+    /// Seconds into the file to start analyzing and playing from
+    #[arg(long, default_value_t = 0.)]
+    start: f64,
+    /// Seconds into the file to stop analyzing at
+    #[arg(long, conflicts_with = "seconds")]
+    end: Option<f64>,
     /// Truncate analysis buffer
     #[arg(short, long, default_value_t = 8192)]
     top: usize,
@@ -53,9 +113,210 @@ pub struct Cli {
     /// STFT jump size
     #[arg(short, long, default_value_t = false)]
     play_audio: bool,
+    /// Draw sinusoidal partial tracks over the spectrogram
+    #[arg(long, default_value_t = false)]
+    partials: bool,
+    /// Export partial tracks as CSV
+    #[arg(long)]
+    export_partials: Option<String>,
+    /// Export long-term average and max-hold spectra as PNG or SVG
+    #[arg(long)]
+    export_spectrum: Option<String>,
+    /// Frequency weighting for display and band levels
+    #[arg(long, value_enum, default_value_t = Weighting::Z)]
+    weighting: Weighting,
+    /// Flatten the spectrogram before colour mapping
+    #[arg(long, value_enum, default_value_t = DisplayMode::Absolute)]
+    display_mode: DisplayMode,
+    /// Show fractional-octave band levels
+    #[arg(long, value_enum)]
+    octave_bands: Option<Fraction>,
+    /// Export band levels over time as CSV
+    #[arg(long)]
+    export_bands: Option<String>,
+    /// Write an exponential sine sweep to a WAV file and exit
+    #[arg(long)]
+    generate_sweep: Option<String>,
+    /// Start frequency of the sweep in Hz
+    #[arg(long, default_value_t = 20.)]
+    sweep_start: f64,
+    /// End frequency of the sweep in Hz
+    #[arg(long, default_value_t = 20000.)]
+    sweep_end: f64,
+    /// Length of the sweep in seconds
+    #[arg(long, default_value_t = 10.)]
+    sweep_seconds: f64,
+    /// Sample rate of the generated sweep
+    #[arg(long, default_value_t = 48000)]
+    sweep_rate: u32,
+    /// Deconvolve the audio file, a recorded sweep, into an impulse response
+    #[arg(long, default_value_t = false)]
+    impulse_response: bool,
+    /// Export the impulse response as a WAV file
+    #[arg(long)]
+    export_ir: Option<String>,
+    /// Show octave band energy decay curves of an impulse response
+    #[arg(long, default_value_t = false)]
+    decay: bool,
+    /// Export reverberation times and clarity per octave band as CSV
+    #[arg(long)]
+    export_decay: Option<String>,
+    /// Measure harmonic distortion and noise of a sine recording
+    #[arg(long, default_value_t = false)]
+    distortion: bool,
+    /// Estimate the noise floor and report signal-to-noise ratios
+    #[arg(long, value_enum)]
+    noise_floor: Option<NoiseEstimator>,
+    /// Export broadband and band signal-to-noise ratios as CSV
+    #[arg(long)]
+    export_noise: Option<String>,
+    /// Mark clipping, DC offset, dropouts and clicks on the timeline
+    #[arg(long, default_value_t = false)]
+    defects: bool,
+    /// Split the file into segments separated by silence
+    #[arg(long, default_value_t = false, conflicts_with = "impulse_response")]
+    segment: bool,
+    /// Level in dBFS below which audio counts as silence
+    #[arg(long, default_value_t = -50.)]
+    silence_threshold: f32,
+    /// Shortest silence in seconds that splits segments
+    #[arg(long, default_value_t = 0.5)]
+    min_silence: f32,
+    /// Shortest segment in seconds to keep
+    #[arg(long, default_value_t = 1.)]
+    min_segment: f32,
+    /// Export segments as an Audacity label file
+    #[arg(long, conflicts_with = "impulse_response")]
+    export_labels: Option<String>,
+    /// Export each segment as a WAV file into a directory, created if missing
+    #[arg(long, conflicts_with = "impulse_response")]
+    export_segments: Option<String>,
+    /// Mark voice activity and classify speech and music
+    #[arg(long, default_value_t = false)]
+    activity: bool,
+    /// Track formants and draw the LPC envelope on the spectrum slice
+    #[arg(long, default_value_t = false)]
+    formants: bool,
+    /// Order of the LPC envelope, by default two plus the sample rate in kHz
+    #[arg(long)]
+    lpc_order: Option<usize>,
+    /// Export formant tracks as CSV
+    #[arg(long)]
+    export_formants: Option<String>,
+    /// Estimate the key and show chords above the spectrogram
+    #[arg(long, default_value_t = false)]
+    chords: bool,
+    /// Offer a view of the third-octave modulation spectrum
+    #[arg(long, default_value_t = false)]
+    modulation: bool,
+    /// Resample to this rate before analysis, so files recorded at
+    /// different rates share a frequency grid
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1000..))]
+    analysis_rate: Option<u32>,
+    /// How times are shown and exported
+    #[arg(long, value_enum, default_value_t = TimeFormat::Seconds)]
+    time_format: TimeFormat,
+    /// Frame rate of SMPTE timecode
+    #[arg(long, default_value_t = 30)]
+    smpte_fps: u32,
+}
+
+/// Parse the command line, rejecting what clap can't check on its own.
+fn parse_cli() -> Cli {
+    let cli = Cli::parse();
+
+    if let Some(end) = cli.end.filter(|end| *end <= cli.start) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!("--end {end} must come after --start {}", cli.start),
+            )
+            .exit();
+    }
+    cli
+}
+
+/// Log whether an export file was written.
+fn report<E: std::fmt::Display>(path: &str, result: std::result::Result<(), E>) {
+    match result {
+        Ok(()) => log::info!("Exported {path}"),
+        Err(e) => log::error!("Failed to export {path}: {e}"),
+    }
+}
+
+/// Write an export file, logging any failure.
+fn export(path: &str, write: impl FnOnce(BufWriter<File>) -> std::io::Result<()>) {
+    report(
+        path,
+        File::create(path).and_then(|file| write(BufWriter::new(file))),
+    );
+}
+
+/// Write a mono WAV export, logging any failure.
+fn export_wav(path: &str, signal: &[f32], sample_rate: u32) {
+    report(path, save_wav(path, signal, sample_rate));
+}
+
+/// STFT frames of another audio track over the same stretch of the file,
+/// resampled to the rate of the first so the frames line up.
+async fn analyze_track(
+    cli: &Cli,
+    index: usize,
+    offset: f64,
+    seconds: Option<f32>,
+    sample_rate: u32,
+) -> error::Result<Track> {
+    let mut audio = AudioFile::open_track(&cli.audio_file, Some(index)).await?;
+    let skip = if offset > 0. { audio.seek(offset)? } else { 0 };
+    let mut streaming = StreamingStft::new("hamming", cli.window_size, cli.jump_size);
+    let resampler = Resampler::new(audio.sample_rate(), sample_rate, 1);
+    let frames = resampler
+        .stream(audio.stream_mono(skip, seconds))
+        .flat_map(|chunk| streaming.push(&chunk))
+        .collect();
+
+    Ok(Track {
+        info: audio.track().clone(),
+        frames,
+    })
+}
+
+/// Rows of the gui's file info panel.
+fn file_info(audio: &AudioFile, timecode: &Timecode) -> Vec<(&'static str, String)> {
+    let metadata = audio.metadata();
+    let track = audio.track();
+    let mut info = Vec::new();
+
+    if let Some(title) = &metadata.title {
+        info.push(("Title", title.clone()));
+    }
+    if let Some(artist) = &metadata.artist {
+        info.push(("Artist", artist.clone()));
+    }
+    if let Some(album) = &metadata.album {
+        info.push(("Album", album.clone()));
+    }
+    info.push(("Codec", track.codec.clone()));
+    info.push(("Sample rate", format!("{} Hz", audio.sample_rate())));
+    info.push(("Channels", audio.channels().to_string()));
+    if let Some(bits) = audio.bits_per_sample() {
+        info.push(("Bit depth", format!("{bits} bit")));
+    }
+    if let Some(bitrate) = audio.stream_bitrate() {
+        info.push(("Bitrate", format!("{:.0} kb/s", bitrate / 1000.)));
+    } else if let Some(bitrate) = audio.file_bitrate() {
+        info.push((
+            "Bitrate",
+            format!("{:.0} kb/s file average", bitrate / 1000.),
+        ));
+    }
+    if let Some(duration) = audio.duration() {
+        info.push(("Duration", timecode.format(duration)));
+    }
+    info
 }
 
-/// Launch winit or wasm.
+/// Launch winit or wasm, exiting with a failure status on errors.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub async fn main() {
     // Configure logging
@@ -63,19 +324,70 @@ pub async fn main() {
     {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
         console_log::init_with_level(log::Level::Info).unwrap();
+
+        if let Err(e) = run(parse_cli()).await {
+            log::error!("{e}");
+        }
     }
     #[cfg(not(target_arch = "wasm32"))]
     {
-        let _log = tailog::init();
+        // The log is dropped, and so flushed, before exiting.
+        let failed = {
+            let _log = tailog::init();
+            let result = run(parse_cli()).await;
+
+            if let Err(e) = &result {
+                log::error!("{e}");
+            }
+            result.is_err()
+        };
+
+        if failed {
+            std::process::exit(1);
+        }
     }
+}
 
-    let cli = Cli::parse();
-    let event_loop = EventLoop::new().unwrap();
+async fn run(cli: Cli) -> error::Result<()> {
+    if let Some(path) = &cli.generate_sweep {
+        let sweep = Sweep {
+            start_hz: cli.sweep_start,
+            end_hz: cli.sweep_end,
+            seconds: cli.sweep_seconds,
+            sample_rate: cli.sweep_rate,
+        };
+
+        export_wav(path, &sweep.signal(), sweep.sample_rate);
+        return Ok(());
+    }
+
+    if cli.list_tracks {
+        let tracks = AudioFile::list_tracks(&cli.audio_file)
+            .await
+            .inspect_err(|_| log::error!("Failed to open {}", cli.audio_file))?;
+
+        for track in tracks {
+            println!("{track}");
+        }
+        return Ok(());
+    }
+
+    let mut audio = match AudioFile::open_track(&cli.audio_file, cli.track.first().copied()).await {
+        Ok(audio) => audio,
+        Err(e) => {
+            log::error!("Failed to open {}", cli.audio_file);
+            return Err(e);
+        }
+    };
+
+    let event_loop = EventLoopBuilder::<UserEvent>::with_user_event()
+        .build()
+        .map_err(|e| PhonolyzeError::Window(e.to_string()))?;
     let window = WindowBuilder::new()
         .with_maximized(true)
         //.with_inner_size(winit::dpi::PhysicalSize::new(1280, 960))
         .build(&event_loop)
-        .unwrap();
+        .map_err(|e| PhonolyzeError::Window(e.to_string()))?;
 
     #[cfg(target_arch = "wasm32")]
     //if cfg!(target_arch = "wasm32")
@@ -102,17 +414,40 @@ pub async fn main() {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    let audio_player = crate::audio::AudioPlayer::from(&cli);
+    let audio_player = crate::audio::AudioPlayer::try_from(&cli)?;
 
-    let mut ctx = RenderView::new(&window).await;
+    let mut ctx = RenderView::new(&window).await?;
 
     #[cfg(not(target_arch = "wasm32"))]
     {
         ctx.state.progress = Some(audio_player.progress.clone());
     }
 
+    ctx.state.timecode = Timecode {
+        format: cli.time_format,
+        sample_rate: audio.sample_rate(),
+        fps: cli.smpte_fps,
+    };
+    ctx.state.file_info = file_info(&audio, &ctx.state.timecode);
+
+    let cover = match &audio.metadata().cover {
+        Some(data) if cli.cover_art => image::load_from_memory(data)
+            .map_err(|e| log::warn!("Failed to load the cover art: {e}"))
+            .ok(),
+        None if cli.cover_art => {
+            log::warn!("{} has no cover art", cli.audio_file);
+            None
+        }
+        _ => None,
+    };
     //let background_image = load_image("images/noise3.png").await.unwrap();
-    let background_image = load_image("images/baba.png").await.unwrap();
+    let background_image = match cover {
+        Some(cover) => cover,
+        None => load_image("images/baba.png").await.unwrap_or_else(|e| {
+            log::warn!("Failed to load the background image: {e}");
+            image::DynamicImage::new_rgba8(1, 1)
+        }),
+    };
 
     let background_image_pass = Box::new(ScaledImagePass::new(
         background_image,
@@ -122,32 +457,476 @@ pub async fn main() {
         LayerMode::Background,
     ));
 
-    let mut audio = AudioFile::open(&cli.audio_file).await.unwrap();
-    let signal = audio.dump_mono(cli.seconds);
-    dbg!(&signal.len());
-    let analysis = stft(&signal, "hamming", cli.window_size, cli.jump_size);
-    dbg!(cli.window_size, cli.jump_size);
-    dbg!(&analysis.0.len(), &analysis.0[0].len());
+    // Times would all be off if the analysis started anywhere else.
+    let (offset, skip) = if cli.start > 0. {
+        let frames = audio
+            .seek(cli.start)
+            .inspect_err(|_| log::error!("Failed to seek to {} s", cli.start))?;
+
+        (cli.start, frames)
+    } else {
+        (0., 0)
+    };
+
+    let duration = match cli.end {
+        Some(end) => Some((end - offset) as f32),
+        None => cli.seconds,
+    };
+
+    // Analyses of the samples themselves need the whole signal in memory.
+    // Otherwise only one window is kept while frames are computed as the
+    // packets are decoded.
+    let needs_signal = cli.impulse_response
+        || cli.decay
+        || cli.export_decay.is_some()
+        || cli.distortion
+        || cli.defects
+        || cli.segment
+        || cli.export_labels.is_some()
+        || cli.export_segments.is_some()
+        || cli.activity;
+    // Analyses of the whole spectrogram wait for every frame.  Without any,
+    // the window opens right away and a worker thread fills it in, as long
+    // as the length is known up front.
+    let needs_frames = cli.modulation
+        || cli.partials
+        || cli.export_partials.is_some()
+        || cli.export_spectrum.is_some()
+        || cli.noise_floor.is_some()
+        || cli.export_noise.is_some()
+        || cli.octave_bands.is_some()
+        || cli.export_bands.is_some()
+        || cli.formants
+        || cli.export_formants.is_some()
+        || cli.chords
+        || cli.track.len() > 1;
+    let sample_rate = audio.sample_rate();
+    let analysis_rate = cli.analysis_rate.unwrap_or(sample_rate);
+    let background_samples = worker::expected_samples(&audio, offset, duration, analysis_rate)
+        .filter(|_| !needs_signal && !needs_frames && cfg!(not(target_arch = "wasm32")));
+    // Analyses of the samples keep the file's own rate, and only the STFT
+    // input is resampled.  The low-pass filter would smear clicks and
+    // clipping, and drop harmonics above the new Nyquist frequency.
+    let mut signal = Vec::new();
+    let mut frames = Vec::new();
+    let mut length = background_samples.unwrap_or(0);
+    let mut streaming = StreamingStft::new("hamming", cli.window_size, cli.jump_size);
+
+    if background_samples.is_none() {
+        if needs_signal {
+            signal = audio.stream_mono(skip, duration).flatten().collect();
+        } else {
+            let resampler = Resampler::new(sample_rate, analysis_rate, 1);
+
+            for chunk in resampler.stream(audio.stream_mono(skip, duration)) {
+                length += chunk.len();
+                frames.extend(streaming.push(&chunk));
+            }
+        }
+        ctx.state.add_corrupt_packets(audio.corrupt_packets());
+    }
+
+    let mut waveform_pass = None;
+
+    if cli.impulse_response {
+        let sweep = Sweep {
+            start_hz: cli.sweep_start,
+            end_hz: cli.sweep_end,
+            seconds: cli.sweep_seconds,
+            sample_rate,
+        };
+        signal = sweep.impulse_response(&signal);
+
+        if let Some(path) = &cli.export_ir {
+            export_wav(path, &signal, sample_rate);
+        }
+
+        ctx.state.overlays.waveform = Some(true);
+        waveform_pass = Some(Box::new(WaveformPass::new(&signal, &ctx)));
+    }
+    let resampled: Cow<[f32]> = if needs_signal && analysis_rate != sample_rate {
+        let mut resampler = Resampler::new(sample_rate, analysis_rate, 1);
+        let mut resampled = resampler.process(&signal);
+
+        resampled.extend(resampler.flush());
+        Cow::Owned(resampled)
+    } else {
+        Cow::Borrowed(&signal)
+    };
+    let analysis = if needs_signal {
+        length = resampled.len();
+        stft(&resampled, "hamming", cli.window_size, cli.jump_size)
+    } else {
+        (frames, Vec::new())
+    };
+    log::debug!(
+        "{} frames of {} samples every {} from {length} samples",
+        analysis.0.len(),
+        cli.window_size,
+        cli.jump_size
+    );
 
     #[cfg(not(target_arch = "wasm32"))]
     if let Ok(mut progress) = audio_player.progress.lock() {
-        progress.music_length = signal.len() as f64 / audio.sample_rate() as f64;
+        progress.music_start = offset;
+        progress.music_length = match audio.duration() {
+            Some(total) => {
+                let rest = (total - offset).max(0.);
+
+                duration.map_or(rest, |seconds| rest.min(seconds as f64))
+            }
+            None => length as f64 / analysis_rate as f64,
+        };
     }
 
-    dbg!(&analysis.0.len(), &analysis.0[0].len());
+    let grid = StftGrid {
+        sample_rate: analysis_rate,
+        window_size: cli.window_size,
+        hop_size: cli.jump_size,
+        offset,
+    };
+    // Times of the samples of `signal`
+    let samples = StftGrid {
+        sample_rate,
+        ..grid
+    };
 
-    let analysis_pass = Box::new(AnalysisLayerPass::new(
-        &analysis.0,
-        &ctx,
-        LayerMode::AlphaBlend,
-        Gradient::new(
-            Some("InitGradient"),
-            ColorMap::default().uniform(),
-            &ctx.device,
-            &ctx.queue,
+    let background_frames = background_samples
+        .map(|samples| worker::frame_count(samples, cli.window_size, cli.jump_size));
+
+    if background_frames.unwrap_or(analysis.0.len()) == 0 {
+        return Err(PhonolyzeError::TooShort(cli.window_size));
+    }
+    let gradient = Gradient::new(
+        Some("InitGradient"),
+        ColorMap::default().uniform(),
+        &ctx.device,
+        &ctx.queue,
+    );
+    let analysis_pass = Box::new(match background_frames {
+        Some(frames) => AnalysisLayerPass::with_capacity(
+            frames.max(2),
+            cli.window_size / 2 + 1,
+            &ctx,
+            LayerMode::AlphaBlend,
+            gradient,
         ),
+        None => AnalysisLayerPass::new(&analysis.0, &ctx, LayerMode::AlphaBlend, gradient),
+    });
+
+    let mut modulation_pass = None;
+
+    if cli.modulation {
+        let bands = bands::bands(Fraction::Third, grid.sample_rate);
+        let spectrum = modulation::modulation_spectrum(&analysis.0, &bands, &grid);
+
+        ctx.state.views.push(View::Modulation);
+        modulation_pass = Some(Box::new(ModulationPass::new(&spectrum, &bands, &ctx)));
+    }
+
+    let mut tracks_pass = None;
+    // Every audio track can be picked in the gui.  Those stacked are
+    // analysed now, the others when they are picked.
+    let mut tracks: Vec<Track> = audio
+        .tracks()
+        .into_iter()
+        .map(|info| Track {
+            info,
+            frames: Vec::new(),
+        })
+        .collect();
+
+    if cli.track.len() > 1 && !analysis.0.is_empty() {
+        let mut others = Vec::new();
+
+        for &index in &cli.track[1..] {
+            match analyze_track(&cli, index, offset, duration, analysis_rate).await {
+                Ok(track) => others.push(track),
+                Err(e) => log::error!("Failed to analyze track {index}: {e}"),
+            }
+        }
+
+        let stacked: Vec<_> = std::iter::once((audio.track(), &analysis.0[..]))
+            .chain(others.iter().map(|track| (&track.info, &track.frames[..])))
+            .collect();
+
+        ctx.state.views.push(View::Tracks);
+        tracks_pass = Some(Box::new(TracksPass::new(&stacked, &ctx)));
+
+        for track in others {
+            if let Some(slot) = tracks.get_mut(track.info.index) {
+                slot.frames = track.frames;
+            }
+        }
+    }
+    if tracks.len() > 1 {
+        ctx.state.track = audio.track().index;
+        ctx.state.tracks = tracks;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ctx.state.track_source = Some(worker::TrackSource {
+                path: cli.audio_file.clone(),
+                offset,
+                seconds: duration,
+                sample_rate: analysis_rate,
+                window_size: cli.window_size,
+                hop_size: cli.jump_size,
+                proxy: event_loop.create_proxy(),
+            });
+        }
+    }
+
+    let mut partials_pass = None;
+
+    if cli.partials || cli.export_partials.is_some() {
+        let tracks = partials::track_partials(&analysis.0, &Default::default());
+
+        if let Some(path) = &cli.export_partials {
+            export(path, |writer| {
+                partials::write_csv(&tracks, &grid, &ctx.state.timecode, writer)
+            });
+        }
+
+        if cli.partials {
+            ctx.state.overlays.partials = Some(true);
+            partials_pass = Some(Box::new(PartialsPass::new(
+                &tracks,
+                analysis.0.len(),
+                cli.window_size / 2 + 1,
+                &ctx,
+            )));
+        }
+    }
+
+    let average = spectrum::long_term_average(&analysis.0);
+    let max_hold = spectrum::max_hold(&analysis.0);
+
+    if let Some(path) = &cli.export_spectrum {
+        let curves = [
+            Curve {
+                label: "Long-term average",
+                levels: &average,
+                color: plotters::style::BLUE,
+            },
+            Curve {
+                label: "Max hold",
+                levels: &max_hold,
+                color: plotters::style::RED,
+            },
+        ];
+
+        report(
+            path,
+            plot::export_spectra(path, &cli.audio_file, &curves, &grid),
+        );
+    }
+
+    ctx.state.overlays.ltas = Some(false);
+    let ltas_pass = Box::new(LtasPass::new(
+        &average,
+        &max_hold,
+        analysis.0.len(),
+        &grid,
+        &ctx,
     ));
 
+    ctx.state.overlays.slice = Some(false);
+    let mut slice_pass = SlicePass::new(&grid, &ctx);
+
+    if cli.noise_floor.is_some() || cli.export_noise.is_some() {
+        let floor = cli
+            .noise_floor
+            .unwrap_or_default()
+            .floor(&analysis.0, &grid);
+        let bands = bands::bands(cli.octave_bands.unwrap_or_default(), grid.sample_rate);
+        let reports = noise::report(
+            &analysis.0,
+            &floor,
+            &bands,
+            &grid,
+            &cli.weighting.gains(&grid),
+        );
+
+        if let Some(broadband) = reports.first() {
+            log::info!(
+                "Signal {:.2} dB, noise {:.2} dB, SNR {:.2} dB",
+                broadband.signal,
+                broadband.noise,
+                broadband.snr
+            );
+        }
+        if let Some(path) = &cli.export_noise {
+            export(path, |writer| noise::write_csv(&reports, writer));
+        }
+
+        if cli.noise_floor.is_some() {
+            ctx.state.overlays.noise_floor = Some(true);
+            slice_pass = slice_pass.with_noise_floor(floor);
+        }
+    }
+
+    let mut bands_pass = None;
+
+    if cli.octave_bands.is_some() || cli.export_bands.is_some() {
+        let bands = bands::bands(cli.octave_bands.unwrap_or_default(), grid.sample_rate);
+
+        if let Some(path) = &cli.export_bands {
+            let levels = bands::band_levels(&analysis.0, &bands, &grid, cli.weighting);
+            export(path, |writer| {
+                bands::write_csv(&levels, &bands, &grid, &ctx.state.timecode, writer)
+            });
+        }
+
+        if cli.octave_bands.is_some() {
+            ctx.state.overlays.bands = Some(true);
+            let filter = BandFilter::new(&bands, &grid);
+            bands_pass = Some(Box::new(BandsPass::new(filter, &bands, &ctx)));
+        }
+    }
+
+    let mut decay_pass = None;
+
+    if cli.decay || cli.export_decay.is_some() {
+        let octaves = bands::bands(Fraction::Octave, sample_rate);
+        let decays = decay::decays(&signal, sample_rate, &octaves);
+
+        if let Some(path) = &cli.export_decay {
+            export(path, |writer| decay::write_csv(&decays, writer));
+        }
+
+        if cli.decay {
+            ctx.state.overlays.decay = Some(true);
+            decay_pass = Some(Box::new(DecayPass::new(&decays, &ctx)));
+        }
+    }
+
+    let mut distortion_pass = None;
+
+    if cli.distortion {
+        match distortion::analyze(&signal, sample_rate) {
+            Some(result) => {
+                log::info!(
+                    "Fundamental {:.2} Hz at {:.2} dBFS, THD {:.2} dB, THD+N {:.2} dB, SINAD {:.2} dB, SNR {:.2} dB",
+                    result.fundamental.hz,
+                    result.fundamental.level,
+                    result.thd,
+                    result.thd_n,
+                    result.sinad,
+                    result.snr,
+                );
+                for harmonic in &result.harmonics {
+                    log::info!(
+                        "H{} {:.1} Hz at {:.2} dBFS",
+                        harmonic.number,
+                        harmonic.hz,
+                        harmonic.level
+                    );
+                }
+
+                ctx.state.overlays.distortion = Some(true);
+                distortion_pass = Some(Box::new(DistortionPass::new(&result, &ctx)));
+            }
+            None => log::error!("Recording too short for distortion analysis"),
+        }
+    }
+
+    if cli.defects {
+        let found = defects::detect(&signal, sample_rate);
+
+        log::info!("Found {} defects", found.len());
+        ctx.state.overlays.markers = Some(true);
+        ctx.state
+            .markers
+            .extend(found.iter().map(|defect| Marker::defect(defect, &samples)));
+    }
+
+    let mut segments_pass = None;
+
+    if cli.segment || cli.export_labels.is_some() || cli.export_segments.is_some() {
+        let params = SilenceParams {
+            threshold_db: cli.silence_threshold,
+            min_silence: cli.min_silence,
+            min_segment: cli.min_segment,
+            ..Default::default()
+        };
+        let found = segments::segments(&signal, sample_rate, &params);
+
+        log::info!("Found {} segments", found.len());
+        if let Some(path) = &cli.export_labels {
+            export(path, |writer| {
+                segments::write_labels(&found, &samples, writer)
+            });
+        }
+        if let Some(dir) = &cli.export_segments {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                log::error!("Failed to create {dir}: {e}");
+            }
+            for (i, segment) in found.iter().enumerate() {
+                let path = format!("{dir}/segment_{:03}.wav", i + 1);
+
+                export_wav(&path, &signal[segment.start..segment.end], sample_rate);
+            }
+        }
+
+        if cli.segment {
+            ctx.state.overlays.segments = Some(true);
+            segments_pass = Some(Box::new(SegmentsPass::new(&found, &samples, &ctx)));
+        }
+    }
+
+    let mut activity_pass = None;
+
+    if cli.activity {
+        let classes = activity::classify(&resampled, &analysis.0, &grid);
+        let seconds = |class| {
+            classes.iter().filter(|c| **c == class).count() as f64 * grid.hop_size as f64
+                / grid.sample_rate as f64
+        };
+
+        log::info!(
+            "Speech {:.1} s, music {:.1} s",
+            seconds(Activity::Speech),
+            seconds(Activity::Music)
+        );
+        ctx.state.overlays.activity = Some(true);
+        activity_pass = Some(Box::new(ActivityPass::new(&classes, &ctx)));
+    }
+
+    let mut formants_pass = None;
+
+    if cli.formants || cli.export_formants.is_some() {
+        let formants = lpc::formants(&analysis.0, &grid);
+
+        if let Some(path) = &cli.export_formants {
+            export(path, |writer| {
+                lpc::write_csv(&formants, &grid, &ctx.state.timecode, writer)
+            });
+        }
+
+        if cli.formants {
+            let order = cli.lpc_order.unwrap_or(lpc::auto_order(grid.sample_rate));
+
+            ctx.state.overlays.formants = Some(true);
+            ctx.state.overlays.lpc = Some(true);
+            formants_pass = Some(Box::new(FormantsPass::new(&formants, &grid, &ctx)));
+            slice_pass = slice_pass.with_lpc_envelope(order);
+        }
+    }
+
+    let mut chords_pass = None;
+
+    if cli.chords {
+        let chroma = harmony::chroma(&analysis.0, &grid);
+
+        ctx.state.key = harmony::key(&chroma);
+        if let Some(key) = ctx.state.key {
+            log::info!("Key: {key}");
+        }
+        ctx.state.overlays.chords = Some(true);
+        chords_pass = Some(Box::new(ChordsPass::new(&harmony::chords(&chroma), &ctx)));
+    }
+
+    let markers_pass = Box::new(MarkersPass::new(&ctx));
     let meter_pass = Box::new(MeterPass::new(&analysis.0, &ctx));
 
     let gui_pass = Box::new(Gui::new(
@@ -159,14 +938,77 @@ pub async fn main() {
 
     ctx.layers.push(background_image_pass);
     ctx.layers.push(analysis_pass);
+    if let Some(modulation_pass) = modulation_pass {
+        ctx.layers.push(modulation_pass);
+    }
+    if let Some(tracks_pass) = tracks_pass {
+        ctx.layers.push(tracks_pass);
+    }
+    if let Some(partials_pass) = partials_pass {
+        ctx.layers.push(partials_pass);
+    }
+    if let Some(activity_pass) = activity_pass {
+        ctx.layers.push(activity_pass);
+    }
+    if let Some(formants_pass) = formants_pass {
+        ctx.layers.push(formants_pass);
+    }
+    if let Some(chords_pass) = chords_pass {
+        ctx.layers.push(chords_pass);
+    }
+    ctx.layers.push(markers_pass);
+    if let Some(segments_pass) = segments_pass {
+        ctx.layers.push(segments_pass);
+    }
     ctx.layers.push(meter_pass);
+    ctx.layers.push(ltas_pass);
+    ctx.layers.push(Box::new(slice_pass));
+    if let Some(bands_pass) = bands_pass {
+        ctx.layers.push(bands_pass);
+    }
+    if let Some(waveform_pass) = waveform_pass {
+        ctx.layers.push(waveform_pass);
+    }
+    if let Some(decay_pass) = decay_pass {
+        ctx.layers.push(decay_pass);
+    }
+    if let Some(distortion_pass) = distortion_pass {
+        ctx.layers.push(distortion_pass);
+    }
     ctx.layers.push(gui_pass);
 
+    ctx.state.spectrogram = Some(Spectrogram {
+        frames: analysis.0,
+        grid,
+    });
+
+    ctx.state.weighting = cli.weighting;
+    ctx.state.display_mode = cli.display_mode;
+
+    if let Some(frames) = background_frames {
+        ctx.state.analysis_frames = Some(frames);
+        worker::spawn_analysis(
+            audio,
+            skip,
+            duration,
+            analysis_rate,
+            streaming,
+            event_loop.create_proxy(),
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        ctx.state.player = Some(audio_player);
+    }
+
     let mut event_handler = EventHandler::new(&window, ctx);
 
-    let _ = event_loop.run(move |event, elwt| {
-        event_handler.handle_event(event, elwt);
-    });
+    event_loop
+        .run(move |event, elwt| {
+            event_handler.handle_event(event, elwt);
+        })
+        .map_err(|e| PhonolyzeError::Window(e.to_string()))
 }
 
 //let grad = ColorMap::Rgb.grad();
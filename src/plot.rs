@@ -0,0 +1,93 @@
+//! Static plot exports through plotters.
+use std::path::Path;
+
+use anyhow::Result;
+use plotters::{coord::Shift, prelude::*};
+
+use crate::dsp::StftGrid;
+
+const SIZE: (u32, u32) = (1280, 720);
+
+/// Named curve of decibel levels, one per STFT bin.
+pub struct Curve<'a> {
+    pub label: &'a str,
+    pub levels: &'a [f32],
+    pub color: RGBColor,
+}
+
+/// Plot spectra on a log frequency axis, as SVG or PNG depending on the
+/// file extension.
+pub fn export_spectra(path: &str, title: &str, curves: &[Curve], grid: &StftGrid) -> Result<()> {
+    let is_svg = Path::new(path)
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("svg"))
+        .unwrap_or(false);
+
+    if is_svg {
+        draw_spectra(
+            SVGBackend::new(path, SIZE).into_drawing_area(),
+            title,
+            curves,
+            grid,
+        )
+    } else {
+        draw_spectra(
+            BitMapBackend::new(path, SIZE).into_drawing_area(),
+            title,
+            curves,
+            grid,
+        )
+    }
+}
+
+fn draw_spectra<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    title: &str,
+    curves: &[Curve],
+    grid: &StftGrid,
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let min_hz = 20f32;
+    let max_hz = grid.sample_rate as f32 / 2.;
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 24))
+        .margin(16)
+        .x_label_area_size(40)
+        .y_label_area_size(56)
+        .build_cartesian_2d((min_hz..max_hz).log_scale(), -120f32..0f32)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Frequency (Hz)")
+        .y_desc("Level (dB)")
+        .draw()?;
+
+    for curve in curves {
+        let color = curve.color;
+        let points = curve
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(bin, level)| (grid.bin_hz(bin as f32), level.max(-120.)))
+            .filter(|(hz, _)| *hz >= min_hz && *hz <= max_hz);
+
+        chart
+            .draw_series(LineSeries::new(points, &color))?
+            .label(curve.label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    root.present()?;
+
+    Ok(())
+}
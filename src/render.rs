@@ -1,7 +1,11 @@
 use instant::Duration;
 use winit::{dpi::PhysicalSize, window::Window};
 
-use crate::layers::{Layer, LayerState};
+use crate::{
+    error::{PhonolyzeError, Result},
+    layers::{Layer, LayerState},
+    uniforms::Camera,
+};
 
 pub struct Renderer<'a> {
     pub view: &'a wgpu::TextureView,
@@ -25,7 +29,7 @@ pub struct RenderView<'a> {
 }
 
 impl<'a> RenderView<'a> {
-    pub async fn new(window: &'a Window) -> Self {
+    pub async fn new(window: &'a Window) -> Result<Self> {
         let scale_factor = window.scale_factor() as f32;
         let size = window.inner_size();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -36,9 +40,12 @@ impl<'a> RenderView<'a> {
         // SAFETY: `View` is created in the main thread and `window` remains valid
         // for the lifetime of `surface`.
         let surface = unsafe {
+            let target = wgpu::SurfaceTargetUnsafe::from_window(window)
+                .map_err(|e| PhonolyzeError::GpuAdapter(e.to_string()))?;
+
             instance
-                .create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(window).unwrap())
-                .unwrap()
+                .create_surface_unsafe(target)
+                .map_err(|e| PhonolyzeError::GpuAdapter(e.to_string()))?
         };
         println!("--------------->>>>> {:?}", surface);
 
@@ -49,7 +56,7 @@ impl<'a> RenderView<'a> {
                 compatible_surface: Some(&surface),
             })
             .await
-            .unwrap();
+            .ok_or_else(|| PhonolyzeError::GpuAdapter("no compatible adapter".to_string()))?;
 
         let (device, queue) = adapter
             .request_device(
@@ -61,7 +68,7 @@ impl<'a> RenderView<'a> {
                 None,
             )
             .await
-            .unwrap();
+            .map_err(|e| PhonolyzeError::GpuAdapter(e.to_string()))?;
         let capabilities = surface.get_capabilities(&adapter);
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -75,17 +82,21 @@ impl<'a> RenderView<'a> {
         };
 
         surface.configure(&device, &config);
+        let camera = Camera::new(&device);
 
-        RenderView {
+        Ok(RenderView {
             size,
             surface,
             device,
             queue,
             config,
             layers: vec![],
-            state: Default::default(),
+            state: LayerState {
+                camera: Some(camera),
+                ..Default::default()
+            },
             scale_factor,
-        }
+        })
     }
 
     pub fn update(&mut self, delta: Duration, window: &Window) {
@@ -107,7 +118,17 @@ impl<'a> RenderView<'a> {
     }
 
     pub fn render(&mut self, window: &Window) {
-        let frame = self.surface.get_current_texture().unwrap();
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(&self.device, &self.config);
+                return;
+            }
+            Err(e) => {
+                log::error!("{e}");
+                return;
+            }
+        };
         let view = frame
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -1,14 +1,21 @@
-use anyhow::Result;
 use image::DynamicImage;
 use symphonia::core::io::MediaSourceStream;
 
+use crate::error::Result;
+
+/// Network failures count as IO.
+#[cfg(target_arch = "wasm32")]
+fn io(e: impl std::error::Error + Send + Sync + 'static) -> crate::error::PhonolyzeError {
+    std::io::Error::other(e).into()
+}
+
 #[cfg(target_arch = "wasm32")]
 fn format_url(file_name: &str) -> Result<reqwest::Url> {
     let window = web_sys::window().unwrap();
     let origin = window.location().origin().unwrap();
-    let base = reqwest::Url::parse(&origin)?;
+    let base = reqwest::Url::parse(&origin).map_err(io)?;
 
-    Ok(base.join(file_name)?)
+    base.join(file_name).map_err(io)
 }
 
 pub async fn load_image(file_name: &str) -> Result<DynamicImage> {
@@ -17,7 +24,13 @@ pub async fn load_image(file_name: &str) -> Result<DynamicImage> {
         {
             let url = format_url(file_name)?;
 
-            reqwest::get(url).await?.bytes().await?.to_vec()
+            reqwest::get(url)
+                .await
+                .map_err(io)?
+                .bytes()
+                .await
+                .map_err(io)?
+                .to_vec()
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -35,7 +48,13 @@ pub async fn load_sound(file_name: &str) -> Result<MediaSourceStream> {
         #[cfg(target_arch = "wasm32")]
         {
             let url = format_url(file_name)?;
-            let data = reqwest::get(url).await?.bytes().await?.to_vec();
+            let data = reqwest::get(url)
+                .await
+                .map_err(io)?
+                .bytes()
+                .await
+                .map_err(io)?
+                .to_vec();
 
             std::io::Cursor::new(data)
         }
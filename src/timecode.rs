@@ -0,0 +1,108 @@
+//! Times written the same way in the gui and in exports.
+use strum_macros::{Display, EnumIter};
+
+/// How times are written.
+#[derive(Copy, Clone, Debug, Default, EnumIter, Display, PartialEq, clap::ValueEnum)]
+pub enum TimeFormat {
+    /// Seconds to the millisecond
+    #[default]
+    Seconds,
+    /// HH:MM:SS.mmm
+    Clock,
+    /// Sample index at the file's rate
+    Samples,
+    /// Non-drop HH:MM:SS:FF at a video frame rate
+    #[strum(serialize = "SMPTE")]
+    Smpte,
+}
+
+/// Formatter for times in a file.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Timecode {
+    pub format: TimeFormat,
+    pub sample_rate: u32,
+    /// Frames per second of SMPTE timecode
+    pub fps: u32,
+}
+
+impl Default for Timecode {
+    fn default() -> Self {
+        Timecode {
+            format: TimeFormat::default(),
+            sample_rate: 48000,
+            fps: 30,
+        }
+    }
+}
+
+impl Timecode {
+    /// Heading of a time column in CSV exports.
+    pub fn header(&self) -> &'static str {
+        match self.format {
+            TimeFormat::Seconds => "time_s",
+            TimeFormat::Clock => "time",
+            TimeFormat::Samples => "sample",
+            TimeFormat::Smpte => "timecode",
+        }
+    }
+
+    /// A time in an export column headed by `header`.  Seconds keep
+    /// microseconds so that short hops stay apart.
+    pub fn format_export(&self, seconds: f64) -> String {
+        match self.format {
+            TimeFormat::Seconds => format!("{:.6}", seconds.max(0.)),
+            _ => self.format(seconds),
+        }
+    }
+
+    pub fn format(&self, seconds: f64) -> String {
+        let seconds = seconds.max(0.);
+
+        match self.format {
+            TimeFormat::Seconds => format!("{seconds:.3}"),
+            TimeFormat::Clock => {
+                let ms = (seconds * 1000.).round() as u64;
+                let (h, m, s) = hms(ms / 1000);
+
+                format!("{h:02}:{m:02}:{s:02}.{:03}", ms % 1000)
+            }
+            TimeFormat::Samples => format!("{}", (seconds * self.sample_rate as f64).round()),
+            TimeFormat::Smpte => {
+                let fps = self.fps.max(1) as u64;
+                let frames = (seconds * fps as f64).floor() as u64;
+                let (h, m, s) = hms(frames / fps);
+
+                format!("{h:02}:{m:02}:{s:02}:{:02}", frames % fps)
+            }
+        }
+    }
+}
+
+/// Hours, minutes and seconds in a whole number of seconds.
+fn hms(seconds: u64) -> (u64, u64, u64) {
+    (seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let timecode = |format| Timecode {
+            format,
+            sample_rate: 44100,
+            fps: 25,
+        };
+        let seconds = 3723.5;
+
+        assert_eq!(timecode(TimeFormat::Seconds).format(seconds), "3723.500");
+        assert_eq!(
+            timecode(TimeFormat::Seconds).format_export(seconds),
+            "3723.500000"
+        );
+        assert_eq!(timecode(TimeFormat::Clock).format(seconds), "01:02:03.500");
+        assert_eq!(timecode(TimeFormat::Samples).format(seconds), "164206350");
+        assert_eq!(timecode(TimeFormat::Smpte).format(seconds), "01:02:03:12");
+    }
+}
@@ -64,6 +64,29 @@ impl Camera {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.inner]));
     }
 
+    /// Move horizontally so that an analysis position is in the middle of
+    /// the window.
+    pub fn center_x(&mut self, x: f32, queue: &wgpu::Queue) {
+        self.inner.position[0] = 0.5 / self.inner.scale[0] - x;
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.inner]));
+    }
+
+    /// Map analysis coordinates to normalized window coordinates.
+    pub fn project(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            (point[0] + self.inner.position[0]) * self.inner.scale[0],
+            (point[1] + self.inner.position[1]) * self.inner.scale[1],
+        ]
+    }
+
+    /// Map normalized window coordinates to analysis coordinates.
+    pub fn unproject(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            point[0] / self.inner.scale[0] - self.inner.position[0],
+            point[1] / self.inner.scale[1] - self.inner.position[1],
+        ]
+    }
+
     pub fn bind_group_entry(index: u32) -> wgpu::BindGroupLayoutEntry {
         wgpu::BindGroupLayoutEntry {
             binding: index,
@@ -0,0 +1,121 @@
+//! Decoding and STFT on a background thread, so the window can show the
+//! spectrogram while it fills in.
+use winit::event_loop::EventLoopProxy;
+
+use crate::{audio::AudioFile, dsp::resample::Resampler, error::Result, fft::StreamingStft};
+
+/// Frames collected before they are sent to the event loop.
+const BATCH_FRAMES: usize = 64;
+
+/// Events sent to the event loop from other threads.
+#[derive(Debug)]
+pub enum UserEvent {
+    /// Magnitudes of newly analysed frames, in order
+    Frames(Vec<Vec<f32>>),
+    /// Times in seconds of packets that failed to decode
+    CorruptPackets(Vec<f64>),
+    /// The worker has decoded everything
+    AnalysisDone,
+}
+
+/// Number of STFT frames in a signal of `samples`.
+pub fn frame_count(samples: usize, window_size: usize, hop_size: usize) -> usize {
+    match samples.checked_sub(window_size) {
+        Some(rest) => rest / hop_size + 1,
+        None => 0,
+    }
+}
+
+/// Samples at `sample_rate` from `offset` seconds to the end of the track,
+/// or to `seconds` after it, if the container knows the length.
+pub fn expected_samples(
+    audio: &AudioFile,
+    offset: f64,
+    seconds: Option<f32>,
+    sample_rate: u32,
+) -> Option<usize> {
+    let rate = audio.sample_rate();
+    let n = audio.n_frames()?;
+    let rest = n.saturating_sub((offset * rate as f64).round() as u64) as usize;
+    let rest = match seconds {
+        Some(seconds) => rest.min((seconds * rate as f32).round() as usize),
+        None => rest,
+    };
+
+    Some((rest as f64 * sample_rate as f64 / rate as f64).ceil() as usize)
+}
+
+/// The stretch of a file and the STFT settings of the spectrogram, for
+/// analysing tracks picked later in the gui the same way.
+#[derive(Clone, Debug)]
+pub struct TrackSource {
+    pub path: String,
+    pub offset: f64,
+    pub seconds: Option<f32>,
+    pub sample_rate: u32,
+    pub window_size: usize,
+    pub hop_size: usize,
+    pub proxy: EventLoopProxy<UserEvent>,
+}
+
+impl TrackSource {
+    /// Analyse the audio track at `index` on a new thread, returning how
+    /// many frames to expect, or 0 if the length is unknown.
+    pub fn spawn(&self, index: usize) -> Result<usize> {
+        let mut audio = pollster::block_on(AudioFile::open_track(&self.path, Some(index)))?;
+        let skip = if self.offset > 0. {
+            audio.seek(self.offset)?
+        } else {
+            0
+        };
+        let frames = expected_samples(&audio, self.offset, self.seconds, self.sample_rate)
+            .map_or(0, |samples| {
+                frame_count(samples, self.window_size, self.hop_size)
+            });
+
+        spawn_analysis(
+            audio,
+            skip,
+            self.seconds,
+            self.sample_rate,
+            StreamingStft::new("hamming", self.window_size, self.hop_size),
+            self.proxy.clone(),
+        );
+
+        Ok(frames)
+    }
+}
+
+/// Stream the rest of the file through the STFT at `sample_rate` on a new
+/// thread, sending the frames in batches.  Stops early if the event loop
+/// has gone.
+pub fn spawn_analysis(
+    mut audio: AudioFile,
+    skip: usize,
+    seconds: Option<f32>,
+    sample_rate: u32,
+    mut stft: StreamingStft,
+    proxy: EventLoopProxy<UserEvent>,
+) {
+    std::thread::spawn(move || {
+        let mut batch = Vec::with_capacity(BATCH_FRAMES);
+
+        let resampler = Resampler::new(audio.sample_rate(), sample_rate, 1);
+
+        for chunk in resampler.stream(audio.stream_mono(skip, seconds)) {
+            batch.extend(stft.push(&chunk));
+
+            if batch.len() >= BATCH_FRAMES
+                && proxy
+                    .send_event(UserEvent::Frames(std::mem::take(&mut batch)))
+                    .is_err()
+            {
+                return;
+            }
+        }
+
+        let _ = proxy.send_event(UserEvent::Frames(batch));
+        let _ = proxy.send_event(UserEvent::CorruptPackets(audio.corrupt_packets().to_vec()));
+        let _ = proxy.send_event(UserEvent::AnalysisDone);
+    });
+}